@@ -0,0 +1,223 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use aoc2023::{input::InputProvider, solution};
+use criterion::{black_box, Criterion};
+
+// Time per iteration, keyed by day and stage, as estimated by criterion
+type Timings = BTreeMap<(u8, Stage), Duration>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl Stage {
+    const ALL: [Self; 3] = [Self::Parse, Self::Part1, Self::Part2];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|stage| stage.to_string() == name)
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Stage::Parse => "parse",
+            Stage::Part1 => "part1",
+            Stage::Part2 => "part2",
+        })
+    }
+}
+
+// Remembers which benchmarks ran, as criterion skips those filtered out on the command line
+#[derive(Default)]
+struct Recorder {
+    benched: RefCell<BTreeSet<(u8, Stage)>>,
+}
+
+impl Recorder {
+    fn bench<T>(&self, c: &mut Criterion, day: u8, stage: Stage, mut f: impl FnMut() -> T) {
+        c.bench_function(&benchmark_id(day, stage), |b| {
+            self.benched.borrow_mut().insert((day, stage));
+            b.iter(|| black_box(f()))
+        });
+    }
+
+    // Reads criterion's estimates rather than timing the iterations here, which would
+    // include the warm-up
+    fn timings(&self) -> Timings {
+        self.benched
+            .borrow()
+            .iter()
+            .filter_map(|&(day, stage)| {
+                let path = criterion_dir()
+                    .join(benchmark_id(day, stage).replace('/', "_"))
+                    .join("new")
+                    .join("estimates.json");
+                match read_estimate(&path) {
+                    Some(time) => Some(((day, stage), time)),
+                    None => {
+                        eprintln!("Couldn't read the estimate from {}", path.display());
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+fn benchmark_id(day: u8, stage: Stage) -> String {
+    format!("day{day:02}/{stage}")
+}
+
+// The estimate criterion reports, which is the slope unless it used flat sampling
+fn read_estimate(path: &Path) -> Option<Duration> {
+    let estimates: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    let nanos = estimates["slope"]["point_estimate"]
+        .as_f64()
+        .or_else(|| estimates["mean"]["point_estimate"].as_f64())?;
+    Some(Duration::from_secs_f64(nanos / 1e9))
+}
+
+fn bench_days(c: &mut Criterion, recorder: &Recorder) {
+    let provider = InputProvider::from_env();
+    for puzzle in solution::DAYS {
+        let day = puzzle.day();
+        let input = match provider.load(day) {
+            Ok(input) => input,
+            Err(error) => {
+                eprintln!("Skipping day {day:02}: {error}");
+                continue;
+            }
+        };
+        let parsed = match puzzle.parse(&input) {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                eprintln!("Skipping day {day:02}: {error}");
+                continue;
+            }
+        };
+        recorder.bench(c, day, Stage::Parse, || puzzle.parse(&input));
+        recorder.bench(c, day, Stage::Part1, || parsed.part1());
+        recorder.bench(c, day, Stage::Part2, || parsed.part2());
+    }
+}
+
+// Whether criterion is measuring and writing estimates, following its own reading of the
+// arguments: `cargo test --benches` runs without `--bench`, and runs with `--test`, `--list`
+// or `--profile-time` don't save anything
+fn benchmarking() -> bool {
+    let args: Vec<_> = env::args().skip(1).collect();
+    args.iter().any(|arg| arg == "--bench")
+        && !args
+            .iter()
+            .any(|arg| arg == "--test" || arg == "--list" || arg.starts_with("--profile-time"))
+}
+
+fn criterion_dir() -> PathBuf {
+    env::var_os("CRITERION_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            env::var_os("CARGO_TARGET_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/target")))
+                .join("criterion")
+        })
+}
+
+fn summary_path() -> PathBuf {
+    criterion_dir().join("days-summary.tsv")
+}
+
+fn load_summary(path: &Path) -> Timings {
+    let Ok(summary) = fs::read_to_string(path) else {
+        return Timings::new();
+    };
+    summary
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let day = fields.next()?.parse().ok()?;
+            let stage = Stage::from_name(fields.next()?)?;
+            let nanos = fields.next()?.parse().ok()?;
+            Some(((day, stage), Duration::from_nanos(nanos)))
+        })
+        .collect()
+}
+
+fn save_summary(path: &Path, timings: &Timings) {
+    let summary: String = timings
+        .iter()
+        .map(|((day, stage), time)| format!("{day}\t{stage}\t{}\n", time.as_nanos()))
+        .collect();
+    if let Err(error) = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(path, summary))
+    {
+        eprintln!("Couldn't save summary to {}: {error}", path.display());
+    }
+}
+
+fn print_summary(timings: &Timings, previous: &Timings) {
+    let mut days: Vec<u8> = timings.keys().map(|(day, _)| *day).collect();
+    days.dedup();
+
+    println!();
+    println!(
+        "{:<6}{:>24}{:>24}{:>24}",
+        "Day",
+        Stage::Parse,
+        Stage::Part1,
+        Stage::Part2
+    );
+    for day in days {
+        print!("{day:<6}");
+        for stage in Stage::ALL {
+            let cell = match (timings.get(&(day, stage)), previous.get(&(day, stage))) {
+                (Some(time), Some(before)) => {
+                    let change = time.as_secs_f64() / before.as_secs_f64() - 1.0;
+                    format!("{time:.2?} ({:+.1}%)", change * 100.0)
+                }
+                (Some(time), None) => format!("{time:.2?}"),
+                (None, _) => "-".to_owned(),
+            };
+            print!("{cell:>24}");
+        }
+        println!();
+    }
+    let total: Duration = timings.values().sum();
+    println!("Total: {total:.2?}");
+}
+
+fn main() {
+    let recorder = Recorder::default();
+    let mut criterion = Criterion::default().configure_from_args();
+    bench_days(&mut criterion, &recorder);
+    criterion.final_summary();
+    if !benchmarking() {
+        return;
+    }
+
+    let timings = recorder.timings();
+    if timings.is_empty() {
+        return;
+    }
+    // Keep the timings of days filtered out of this run so the next comparison still has them
+    let path = summary_path();
+    let mut summary = load_summary(&path);
+    print_summary(&timings, &summary);
+    summary.extend(timings);
+    save_summary(&path, &summary);
+}
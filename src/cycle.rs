@@ -0,0 +1,111 @@
+use std::{collections::HashMap, hash::Hash};
+
+use num::{integer::ExtendedGcd, Integer};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<T> {
+    pub start: usize,
+    pub length: usize,
+    states: Vec<T>,
+}
+
+impl<T: Clone + Eq + Hash> Cycle<T> {
+    pub fn detect(initial: T, step: impl FnMut(&T) -> T) -> Self {
+        match Self::detect_within(initial, usize::MAX, step) {
+            Ok(cycle) => cycle,
+            Err(_) => unreachable!("A state must repeat before usize::MAX steps"),
+        }
+    }
+
+    // Returns the state after `steps` if it is reached before any state repeats
+    fn detect_within(initial: T, steps: usize, mut step: impl FnMut(&T) -> T) -> Result<Self, T> {
+        let mut seen = HashMap::new();
+        let mut states = Vec::new();
+        let mut state = initial;
+        for index in 0..=steps {
+            if let Some(&start) = seen.get(&state) {
+                return Ok(Self {
+                    start,
+                    length: index - start,
+                    states,
+                });
+            }
+            if index == steps {
+                break;
+            }
+            let next = step(&state);
+            seen.insert(state.clone(), index);
+            states.push(state);
+            state = next;
+        }
+        Err(state)
+    }
+
+    pub fn state(&self, steps: usize) -> &T {
+        if steps < self.start {
+            &self.states[steps]
+        } else {
+            &self.states[self.start + (steps - self.start) % self.length]
+        }
+    }
+}
+
+pub fn iterate<T: Clone + Eq + Hash>(initial: T, steps: usize, step: impl FnMut(&T) -> T) -> T {
+    match Cycle::detect_within(initial, steps, step) {
+        Ok(cycle) => cycle.state(steps).clone(),
+        Err(state) => state,
+    }
+}
+
+// Combines x ≡ a (mod m) and x ≡ b (mod n) into a single congruence, allowing moduli that
+// aren't coprime
+pub fn chinese_remainder((a, m): (i128, i128), (b, n): (i128, i128)) -> Option<(i128, i128)> {
+    let ExtendedGcd { gcd, x, .. } = m.extended_gcd(&n);
+    if (b - a) % gcd != 0 {
+        return None;
+    }
+    let lcm = m / gcd * n;
+    let multiple = ((b - a) / gcd * x) % (n / gcd);
+    Some(((a + m * multiple).rem_euclid(lcm), lcm))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn step(value: &u32) -> u32 {
+        (value * value + 1) % 1009
+    }
+
+    #[test]
+    fn detect() {
+        let cycle = Cycle::detect(3, step);
+        let mut value = 3;
+        let mut values = vec![];
+        for _ in 0..cycle.start + 2 * cycle.length {
+            values.push(value);
+            value = step(&value);
+        }
+        assert!(values[..cycle.start + cycle.length]
+            .iter()
+            .enumerate()
+            .all(|(index, value)| values.iter().position(|v| v == value) == Some(index)));
+        for (steps, value) in values.iter().enumerate() {
+            assert_eq!(cycle.state(steps), value);
+        }
+    }
+    #[test]
+    fn iterate_past_cycle() {
+        let naive = (0..10_000).fold(3, |value, _| step(&value));
+        assert_eq!(iterate(3, 10_000, step), naive);
+        assert_eq!(iterate(3, 0, step), 3);
+        assert_eq!(iterate(0, 1_000_000_000, |value| (value + 1) % 7), 6);
+    }
+    #[test]
+    fn chinese_remainder_theorem() {
+        assert_eq!(chinese_remainder((2, 3), (3, 5)), Some((8, 15)));
+        assert_eq!(chinese_remainder((1, 4), (3, 6)), Some((9, 12)));
+        assert_eq!(chinese_remainder((1, 4), (2, 6)), None);
+        assert_eq!(chinese_remainder((0, 1), (4, 7)), Some((4, 7)));
+    }
+}
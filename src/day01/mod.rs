@@ -1,15 +1,53 @@
+use crate::{
+    parse::ParseError,
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day01;
+
+impl Solution for Day01 {
+    const DAY: u8 = 1;
+    type Parsed<'a> = &'a str;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Ok(input)
+    }
+    fn part1(input: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(part1(input).into())
+    }
+    fn part2(input: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(part2(input).into())
+    }
+}
+
+pub fn part1(input: &str) -> u32 {
+    input
+        .split('\n')
+        .map(|line| calibration_value_matching(line, &MATCHES[9..]))
+        .sum()
+}
+
+pub fn part2(input: &str) -> u32 {
+    calibration_values_sum(input)
+}
+
 pub fn calibration_values_sum(input: &str) -> u32 {
     input.split('\n').map(calibration_value).sum()
 }
 
 pub fn calibration_value(input: &str) -> u32 {
+    calibration_value_matching(input, &MATCHES)
+}
+
+fn calibration_value_matching(input: &str, matches: &[(&str, u32)]) -> u32 {
     let mut first = None;
     let mut last = None;
 
     for i in 0..input.len() {
-        if let Some(value) = to_value(&input[i..]) {
+        if let Some(value) = to_value(&input[i..], matches) {
             if first.is_none() {
                 first = Some(value);
             }
@@ -41,10 +79,10 @@ const MATCHES: [(&str, u32); 19] = [
     ("9", 9),
 ];
 
-fn to_value(input: &str) -> Option<u32> {
-    for (pattern, value) in MATCHES {
+fn to_value(input: &str, matches: &[(&str, u32)]) -> Option<u32> {
+    for (pattern, value) in matches {
         if input.starts_with(pattern) {
-            return Some(value);
+            return Some(*value);
         }
     }
     None
@@ -53,6 +91,7 @@ fn to_value(input: &str) -> Option<u32> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     #[test]
     fn calibration_value1() {
@@ -83,6 +122,21 @@ mod test {
         "#;
 
         assert_eq!(calibration_values_sum(input), 142);
+        assert_eq!(part1(input), 142);
+    }
+    #[test]
+    fn puzzle_input_part1() {
+        let Some(input) = puzzle_input(1) else {
+            return;
+        };
+        assert_eq!(part1(&input), 54304);
+    }
+    #[test]
+    fn puzzle_input_part2() {
+        let Some(input) = puzzle_input(1) else {
+            return;
+        };
+        assert_eq!(part2(&input), 54418);
     }
     #[test]
     fn sum_of_calabration_values_part_2() {
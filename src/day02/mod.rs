@@ -1,7 +1,34 @@
 use std::str::FromStr;
 
+use crate::{
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day02;
+
+impl Solution for Day02 {
+    const DAY: u8 = 2;
+    type Parsed<'a> = Vec<Game>;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        input
+            .split('\n')
+            .map(Game::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(games: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(possible_total(games).into())
+    }
+    fn part2(games: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(power_total(games).into())
+    }
+}
+
 const CONSTRAINT: Collection = Collection {
     red: 12,
     green: 13,
@@ -9,38 +36,55 @@ const CONSTRAINT: Collection = Collection {
 };
 
 pub fn part1(input: &str) -> i32 {
-    input
-        .split('\n')
-        .map(Game::parse)
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> i32 {
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<i32, ParseError> {
+    Ok(possible_total(&Day02::parse(input)?))
+}
+
+pub fn try_part2(input: &str) -> Result<i32, ParseError> {
+    Ok(power_total(&Day02::parse(input)?))
+}
+
+fn possible_total(games: &[Game]) -> i32 {
+    games
+        .iter()
         .filter(|game| game.possible(CONSTRAINT))
         .map(|game| game.id)
         .sum()
 }
 
-pub fn part2(input: &str) -> i32 {
-    input
-        .split('\n')
-        .map(Game::parse)
+fn power_total(games: &[Game]) -> i32 {
+    games
+        .iter()
         .map(Game::min_possible)
         .map(Collection::power)
         .sum()
 }
 
 #[derive(Clone, Debug)]
-struct Game {
+pub struct Game {
     id: i32,
     attempts: Vec<Collection>,
 }
 
 impl Game {
-    fn parse(input: &str) -> Self {
-        let (game, games) = input.split_once(": ").expect("No colon");
-        let (_, id) = game.split_once(' ').expect("No space between game and id");
-
-        Game {
-            id: id.parse::<i32>().expect("Couldn't parse id as input"),
-            attempts: games.split("; ").map(Collection::parse).collect(),
-        }
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let (game, games) = input.split_once_or(": ", "No colon")?;
+        let (_, id) = game.split_once_or(" ", "No space between game and id")?;
+
+        Ok(Game {
+            id: id.parse_or("Couldn't parse id")?,
+            attempts: games
+                .split("; ")
+                .map(Collection::parse)
+                .collect::<Result<_, _>>()?,
+        })
     }
 
     fn possible(&self, constraint: Collection) -> bool {
@@ -51,7 +95,7 @@ impl Game {
         })
     }
 
-    fn min_possible(self) -> Collection {
+    fn min_possible(&self) -> Collection {
         self.attempts
             .iter()
             .fold(Collection::default(), |acc, attempt| acc.max(attempt))
@@ -66,24 +110,22 @@ struct Collection {
 }
 
 impl Collection {
-    fn parse(input: &str) -> Self {
+    fn parse(input: &str) -> ParseResult<'_, Self> {
         let mut collection = Self::default();
-        input
-            .split(", ")
-            .map(Self::parse_color)
-            .for_each(|(value, color)| collection.with_color(color, value));
+        for color in input.split(", ") {
+            let (value, color) = Self::parse_color(color)?;
+            collection.with_color(color, value);
+        }
 
-        collection
+        Ok(collection)
     }
 
-    fn parse_color(input: &str) -> (i32, Color) {
-        let (val, color) = input
-            .split_once(' ')
-            .expect("No space between color and value");
-        (
-            val.parse::<i32>().unwrap(),
-            Color::from_str(color).expect("Couldn't parse color"),
-        )
+    fn parse_color(input: &str) -> ParseResult<'_, (i32, Color)> {
+        let (val, color) = input.split_once_or(" ", "No space between color and value")?;
+        Ok((
+            val.parse_or("Couldn't parse value")?,
+            Color::from_str(color).map_err(|reason| color.invalid(reason))?,
+        ))
     }
 
     fn with_color(&mut self, color: Color, value: i32) {
@@ -128,6 +170,7 @@ impl FromStr for Color {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
 Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
@@ -142,7 +185,17 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green"#;
 
     #[test]
     fn actual_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 2331);
+        let Some(input) = puzzle_input(2) else {
+            return;
+        };
+        assert_eq!(part1(&input), 2331);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("Game 1: 3 blue\nGame 2: 4 purple").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 11));
+        assert_eq!(error.text, "purple");
     }
 
     #[test]
@@ -152,6 +205,9 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green"#;
 
     #[test]
     fn actual_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 71585);
+        let Some(input) = puzzle_input(2) else {
+            return;
+        };
+        assert_eq!(part2(&input), 71585);
     }
 }
@@ -1,187 +1,130 @@
+use crate::{
+    geometry::Position,
+    grid::Grid,
+    parse::{ParseError, ParseResult},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
-static EMPTY_SYM: Vec<Sym> = Vec::new();
-static EMPTY_NUM: Vec<Number> = Vec::new();
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    const DAY: u8 = 3;
+    type Parsed<'a> = Board;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Board::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(board: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(board.part_number_total().into())
+    }
+    fn part2(board: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(board.gear_total().into())
+    }
+}
 
 pub fn part1(input: &str) -> u32 {
-    let mut board = Board::parse(input);
-    board.mark_numbers();
-    board.sum_marked_numbers()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> u32 {
-    Board::parse(input).gear_total()
+    try_part2(input).unwrap()
 }
 
-#[derive(Debug, Default)]
-struct Board {
-    numbers: Vec<Vec<Number>>,
-    symbols: Vec<Vec<Sym>>,
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+    Ok(Day03::parse(input)?.part_number_total())
 }
 
-impl Board {
-    fn parse(input: &str) -> Self {
-        let mut board = Self::default();
-        input.split('\n').for_each(|line| {
-            let (numbers, syms) = Self::parse_line(line);
-            board.numbers.push(numbers);
-            board.symbols.push(syms);
-        });
-
-        board
-    }
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+    Ok(Day03::parse(input)?.gear_total())
+}
 
-    fn parse_line(input: &str) -> (Vec<Number>, Vec<Sym>) {
+#[derive(Debug, Clone)]
+pub struct Board {
+    schematic: Grid<char>,
+    numbers: Vec<u32>,
+    // The index into numbers of the number covering each cell
+    number_at: Grid<Option<usize>>,
+}
+
+impl Board {
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let schematic = Grid::parse(input, Some, "Unexpected symbol in input")?;
         let mut numbers = Vec::new();
-        let mut symbols = Vec::new();
-        let chars = input.chars().enumerate();
-        let mut current_number: Option<Number> = None;
-        for (index, char) in chars {
-            if char.is_ascii_digit() {
-                if let Some(ref mut num) = current_number {
-                    num.consume_char(char);
-                } else {
-                    current_number = Some(Number {
-                        start_index: index,
-                        len: 1,
-                        value: char.to_digit(10).unwrap(),
-                        marked: false,
-                    });
-                }
-            } else {
-                if let Some(num) = current_number {
-                    numbers.push(num);
-                    current_number = None;
-                }
-                if char != '.' {
-                    symbols.push(Sym::new(index, char));
+        let mut number_at = schematic.map(|_| None);
+        for y in 0..schematic.height() {
+            let mut current_number = None;
+            for (x, c) in schematic.row(y).iter().enumerate() {
+                match c.to_digit(10) {
+                    Some(digit) => {
+                        let index = *current_number.get_or_insert_with(|| {
+                            numbers.push(0);
+                            numbers.len() - 1
+                        });
+                        numbers[index] = numbers[index] * 10 + digit;
+                        number_at[Position(x, y)] = Some(index);
+                    }
+                    None => current_number = None,
                 }
             }
         }
-        if let Some(num) = current_number {
-            numbers.push(num);
-        }
 
-        (numbers, symbols)
+        Ok(Self {
+            schematic,
+            numbers,
+            number_at,
+        })
     }
 
-    fn mark_numbers(&mut self) {
-        for line in 0..self.numbers.len() {
-            let syms = self.symbols_for_line(line);
-            for number in self.numbers[line].iter_mut() {
-                number.maybe_mark_vec(syms.iter());
-            }
-        }
-    }
-
-    fn gear_total(&self) -> u32 {
-        self.symbols
+    fn part_number_total(&self) -> u32 {
+        let mut marked = vec![false; self.numbers.len()];
+        self.schematic
             .iter()
-            .enumerate()
-            .flat_map(|(line, syms)| {
-                let numbers = self.numbers_for_line(line);
-                syms.iter().map(move |sym| sym.gear_ratio(numbers.iter()))
-            })
-            .sum()
-    }
-
-    fn symbols_for_line(&self, line_number: usize) -> Vec<Sym> {
-        self.get_syms(line_number)
-            .chain(self.get_syms(line_number.saturating_sub(1)))
-            .chain(self.get_syms(line_number + 1))
-            .copied()
-            .collect()
-    }
-
-    fn numbers_for_line(&self, line_number: usize) -> Vec<Number> {
-        self.get_numbers(line_number)
-            .chain(self.get_numbers(line_number.saturating_sub(1)))
-            .chain(self.get_numbers(line_number + 1))
-            .copied()
-            .collect()
-    }
+            .filter(|(_, c)| Self::is_symbol(**c))
+            .flat_map(|(position, _)| self.adjacent_numbers(position))
+            .for_each(|number| marked[number] = true);
 
-    fn get_syms(&self, line_number: usize) -> impl Iterator<Item = &Sym> {
-        if line_number >= self.symbols.len() {
-            EMPTY_SYM.iter()
-        } else {
-            self.symbols[line_number].iter()
-        }
-    }
-
-    fn get_numbers(&self, line_number: usize) -> impl Iterator<Item = &Number> {
-        if line_number >= self.symbols.len() {
-            EMPTY_NUM.iter()
-        } else {
-            self.numbers[line_number].iter()
-        }
-    }
-
-    fn sum_marked_numbers(&self) -> u32 {
         self.numbers
             .iter()
-            .flat_map(|line| line.iter().filter(|num| num.marked).map(|num| num.value))
+            .zip(marked)
+            .filter(|(_, marked)| *marked)
+            .map(|(value, _)| value)
             .sum()
     }
-}
-
-#[derive(Debug, Clone, Copy)]
-struct Number {
-    start_index: usize,
-    len: usize,
-    value: u32,
-    marked: bool,
-}
-
-impl Number {
-    fn consume_char(&mut self, c: char) {
-        self.len += 1;
-        self.value = self.value * 10 + c.to_digit(10).unwrap()
-    }
 
-    fn maybe_mark(&mut self, symbol: Sym) {
-        self.marked |= self.is_adjacent_to(symbol)
-    }
-
-    fn is_adjacent_to(&self, symbol: Sym) -> bool {
-        self.start_index.saturating_sub(1) <= symbol.index
-            && symbol.index <= self.start_index + self.len
-    }
-
-    fn maybe_mark_vec<'a>(&mut self, symbols: impl Iterator<Item = &'a Sym>) {
-        symbols.for_each(|sym| {
-            self.maybe_mark(*sym);
-        });
+    fn gear_total(&self) -> u32 {
+        self.schematic
+            .iter()
+            .filter(|(_, c)| **c == '*')
+            .map(|(position, _)| match self.adjacent_numbers(position)[..] {
+                [first, second] => self.numbers[first] * self.numbers[second],
+                _ => 0,
+            })
+            .sum()
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-struct Sym {
-    value: char,
-    index: usize,
-}
-
-impl Sym {
-    fn new(index: usize, value: char) -> Self {
-        Self { index, value }
+    fn adjacent_numbers(&self, position: Position) -> Vec<usize> {
+        let mut numbers: Vec<_> = self
+            .schematic
+            .surrounding(position)
+            .filter_map(|neighbour| self.number_at[neighbour])
+            .collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+        numbers
     }
 
-    fn gear_ratio<'a>(&self, numbers: impl Iterator<Item = &'a Number>) -> u32 {
-        if self.value == '*' {
-            let adjacent_numbers: Vec<_> = numbers
-                .filter(|number| number.is_adjacent_to(*self))
-                .collect();
-            if adjacent_numbers.len() == 2 {
-                return adjacent_numbers.first().unwrap().value
-                    * adjacent_numbers.last().unwrap().value;
-            }
-        }
-        0
+    fn is_symbol(c: char) -> bool {
+        c != '.' && !c.is_ascii_digit()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"467..114..
 ...*......
@@ -201,7 +144,16 @@ mod test {
 
     #[test]
     fn puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 539590);
+        let Some(input) = puzzle_input(3) else {
+            return;
+        };
+        assert_eq!(part1(&input), 539590);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("467..\n...*").unwrap_err();
+        assert_eq!((error.line, error.reason), (2, "Inconsistent row length"));
     }
 
     #[test]
@@ -211,6 +163,9 @@ mod test {
 
     #[test]
     fn puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 80703636);
+        let Some(input) = puzzle_input(3) else {
+            return;
+        };
+        assert_eq!(part2(&input), 80703636);
     }
 }
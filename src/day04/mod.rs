@@ -1,25 +1,59 @@
+use crate::{
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day04;
+
+impl Solution for Day04 {
+    const DAY: u8 = 4;
+    type Parsed<'a> = ScratchCards;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        ScratchCards::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(cards: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(cards.points().into())
+    }
+    fn part2(cards: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(cards.score().into())
+    }
+}
+
 pub fn part1(input: &str) -> u32 {
-    input
-        .split('\n')
-        .map(Card::parse)
-        .map(|card| card.score())
-        .sum()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> u32 {
-    ScratchCards::parse(input).score()
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+    Ok(Day04::parse(input)?.points())
 }
 
-struct ScratchCards {
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+    Ok(Day04::parse(input)?.score())
+}
+
+pub struct ScratchCards {
     cards: Vec<Card>,
 }
 
 impl ScratchCards {
-    fn parse(input: &str) -> Self {
-        let cards = input.split('\n').map(Card::parse).collect();
-        Self { cards }
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let cards = input
+            .split('\n')
+            .map(Card::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { cards })
+    }
+
+    fn points(&self) -> u32 {
+        self.cards.iter().map(Card::score).sum()
     }
 
     fn score(&self) -> u32 {
@@ -45,22 +79,21 @@ struct Card {
 }
 
 impl Card {
-    fn parse(input: &str) -> Self {
-        let (_, numbers) = input.split_once(": ").expect("No colon in line");
-        let (winning_numbers, actual_numbers) = numbers
-            .split_once(" | ")
-            .expect("No pipe separating numbers");
-
-        Self {
-            winning_numbers: Self::parse_numbers(winning_numbers),
-            actual_numbers: Self::parse_numbers(actual_numbers),
-        }
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let (_, numbers) = input.split_once_or(": ", "No colon in line")?;
+        let (winning_numbers, actual_numbers) =
+            numbers.split_once_or(" | ", "No pipe separating numbers")?;
+
+        Ok(Self {
+            winning_numbers: Self::parse_numbers(winning_numbers)?,
+            actual_numbers: Self::parse_numbers(actual_numbers)?,
+        })
     }
 
-    fn parse_numbers(input: &str) -> Vec<u32> {
+    fn parse_numbers(input: &str) -> ParseResult<'_, Vec<u32>> {
         input
             .split_whitespace()
-            .map(|i| i.parse().expect("Couldn't parse number"))
+            .map(|i| i.parse_or("Couldn't parse number"))
             .collect()
     }
 
@@ -79,6 +112,7 @@ impl Card {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
 Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
@@ -94,7 +128,10 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11"#;
 
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 21158);
+        let Some(input) = puzzle_input(4) else {
+            return;
+        };
+        assert_eq!(part1(&input), 21158);
     }
 
     #[test]
@@ -104,6 +141,9 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11"#;
 
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 6050769);
+        let Some(input) = puzzle_input(4) else {
+            return;
+        };
+        assert_eq!(part2(&input), 6050769);
     }
 }
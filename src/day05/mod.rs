@@ -1,91 +1,135 @@
-use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
 use std::ops::Range;
 use std::str::FromStr;
 
-use crate::two_iter::TwoIter;
+use crate::{
+    interval::{IntervalSet, PiecewiseLinear},
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
 
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day05;
+
+impl Solution for Day05 {
+    const DAY: u8 = 5;
+    type Parsed<'a> = Pipeline;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Pipeline::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(pipeline: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(pipeline.run_part1().into())
+    }
+    fn part2(pipeline: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        pipeline
+            .run_part2()
+            .map(Answer::from)
+            .map_err(SolveError::new)
+    }
+}
+
 pub fn part1(input: &str) -> i64 {
-    Pipeline::parse(input).run_part1()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> i64 {
-    Pipeline::parse(input).run_part2()
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<i64, ParseError> {
+    Ok(Day05::parse(input)?.run_part1())
+}
+
+pub fn try_part2(input: &str) -> Result<i64, SeedError> {
+    Day05::parse(input)?.run_part2()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeedError {
+    Parse(ParseError),
+    UnpairedSeeds,
+    NoSeeds,
+}
+
+impl From<ParseError> for SeedError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
 }
 
+impl fmt::Display for SeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeedError::Parse(error) => error.fmt(f),
+            SeedError::UnpairedSeeds => write!(f, "Seed ranges must come in pairs"),
+            SeedError::NoSeeds => write!(f, "Seed ranges are all empty"),
+        }
+    }
+}
+
+impl std::error::Error for SeedError {}
+
 #[derive(Debug)]
-struct Pipeline {
+pub struct Pipeline {
     part1_seeds: Vec<i64>,
-    part2_seeds: Vec<Range<i64>>,
-    maps: HashMap<Type, Map>,
+    // None if the seeds can't be read as (start, length) pairs
+    part2_seeds: Option<IntervalSet<i64>>,
+    seed_to_location: PiecewiseLinear<i64>,
 }
 
 impl Pipeline {
-    fn parse(input: &str) -> Self {
-        let (seeds, maps) = input.split_once("\n\n").expect("No seeds");
-        let seeds = seeds
-            .strip_prefix("seeds: ")
-            .unwrap()
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let (seeds, maps) = input.split_once_or("\n\n", "No seeds")?;
+        let seeds_list = seeds.strip_prefix_or("seeds: ", "Expected seeds")?;
+        let seeds = seeds_list
             .split(' ')
-            .map(FromStr::from_str)
-            .collect::<Result<Vec<i64>, _>>()
-            .unwrap();
-
-        Self {
-            part1_seeds: seeds.clone(),
-            part2_seeds: seeds[..]
-                .chunks(2)
-                .map(|range| (range[0])..(range[0] + range[1]))
-                .collect(),
-            maps: maps
-                .split("\n\n")
-                .map(Map::parse)
-                .fold(HashMap::new(), |mut hash_map, map| {
-                    hash_map.insert(map.input, map);
-                    hash_map
-                }),
+            .map(|seed| seed.parse_or("Couldn't parse seed"))
+            .collect::<Result<Vec<i64>, _>>()?;
+        let mut functions = maps
+            .split("\n\n")
+            .map(|map| Map::parse(map).map(|map| (map.input, map)))
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        let mut seed_to_location = PiecewiseLinear::identity();
+        let mut current_type = Type::Seed;
+        while current_type != Type::Location {
+            let Some(map) = functions.remove(&current_type) else {
+                return Err(maps.invalid("Maps don't lead from seed to location"));
+            };
+            seed_to_location = seed_to_location.then(&map.function);
+            current_type = map.output;
         }
+
+        Ok(Self {
+            part2_seeds: (seeds.len() % 2 == 0).then(|| {
+                seeds[..]
+                    .chunks(2)
+                    .map(|range| (range[0])..(range[0] + range[1]))
+                    .collect()
+            }),
+            part1_seeds: seeds,
+            seed_to_location,
+        })
     }
 
     fn run_part1(&self) -> i64 {
         self.part1_seeds
             .iter()
-            .map(|seed| self.map_seed(*seed))
+            .map(|seed| self.seed_to_location.apply(*seed))
             .min()
             .unwrap()
     }
 
-    fn map_seed(&self, seed: i64) -> i64 {
-        let mut value = seed;
-        let mut current_type = Type::Seed;
-        while current_type != Type::Location {
-            value = self.maps[&current_type].map(value);
-            current_type = self.maps[&current_type].output;
-        }
-        value
-    }
-
-    fn run_part2(&self) -> i64 {
-        self.map_seed_part2(self.part2_seeds.clone())
-            .into_iter()
-            .map(|range| range.start)
+    fn run_part2(&self) -> Result<i64, SeedError> {
+        let seeds = self.part2_seeds.as_ref().ok_or(SeedError::UnpairedSeeds)?;
+        self.seed_to_location
+            .image(seeds)
             .min()
-            .unwrap()
-    }
-
-    fn map_seed_part2(&self, seed: Vec<Range<i64>>) -> Vec<Range<i64>> {
-        let mut ranges = seed;
-        let mut current_type = Type::Seed;
-        while current_type != Type::Location {
-            ranges = ranges
-                .into_iter()
-                .flat_map(|value| self.maps[&current_type].map_range(value))
-                .collect();
-            current_type = self.maps[&current_type].output;
-        }
-        ranges
+            .ok_or(SeedError::NoSeeds)
     }
 }
 
@@ -93,123 +137,39 @@ impl Pipeline {
 struct Map {
     input: Type,
     output: Type,
-    mappings: Vec<Mapping>,
+    function: PiecewiseLinear<i64>,
 }
 
 impl Map {
-    fn parse(input: &str) -> Self {
-        let (types, mappings) = input.split_once('\n').expect("Unexpected map");
-        let (input, rest) = types.split_once('-').expect("No from mapping");
-        let (_, rest) = rest.split_once('-').expect("No to mapping");
-        let (output, _) = rest.split_once(' ').expect("No to mapping");
-
-        Self {
-            input: input.parse().unwrap(),
-            output: output.parse().unwrap(),
-            mappings: mappings.split('\n').map(Mapping::parse).collect(),
-        }
-    }
-
-    fn map(&self, value: i64) -> i64 {
-        for mapping in &self.mappings {
-            match mapping.map(value) {
-                Some(value) => return value,
-                _ => continue,
-            }
-        }
-        value
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let (types, mappings) = input.split_once_or("\n", "Unexpected map")?;
+        let (input, rest) = types.split_once_or("-", "No from mapping")?;
+        let (_, rest) = rest.split_once_or("-", "No to mapping")?;
+        let (output, _) = rest.split_once_or(" ", "No to mapping")?;
+
+        Ok(Self {
+            input: input.parse().map_err(|reason| input.invalid(reason))?,
+            output: output.parse().map_err(|reason| output.invalid(reason))?,
+            function: mappings
+                .split('\n')
+                .map(Self::parse_mapping)
+                .collect::<Result<_, _>>()?,
+        })
     }
 
-    fn map_range(&self, initial_value: Range<i64>) -> Vec<Range<i64>> {
-        let mut mapped = Vec::new();
-        let mut unmapped = vec![initial_value];
-        for mapping in &self.mappings {
-            unmapped = unmapped
-                .into_iter()
-                .flat_map(|value| match mapping.map_range(value) {
-                    (Some(value), rest) => {
-                        mapped.push(value);
-                        rest
-                    }
-                    (None, rest) => rest,
-                })
-                .collect();
-        }
-        mapped.extend(unmapped);
-
-        mapped
-    }
-}
-
-#[derive(Debug)]
-struct Mapping {
-    input_range: Range<i64>,
-    diff: i64,
-}
-
-impl Mapping {
-    fn parse(input: &str) -> Self {
-        let mut values = input.split(' ').map(FromStr::from_str).map(Result::unwrap);
-        let to = values.next().unwrap();
-        let from = values.next().unwrap();
-        let range = values.next().unwrap();
-
-        Self {
-            input_range: from..(from + range),
-            diff: to - from,
-        }
-    }
-
-    fn map(&self, value: i64) -> Option<i64> {
-        if self.input_range.contains(&value) {
-            Some(value + self.diff)
-        } else {
-            None
-        }
-    }
+    fn parse_mapping(input: &str) -> ParseResult<'_, (Range<i64>, i64)> {
+        let values = input
+            .split(' ')
+            .map(|value| value.parse_or("Couldn't parse mapping value"))
+            .collect::<Result<Vec<i64>, _>>()?;
+        let [to, from, range] = values[..] else {
+            return Err(input.invalid("Expected three mapping values"));
+        };
 
-    fn map_range(&self, value: Range<i64>) -> (Option<Range<i64>>, Remainder) {
-        match (
-            self.input_range.start.cmp(&value.end),
-            self.input_range.end.cmp(&value.start),
-        ) {
-            (Ordering::Greater | Ordering::Equal, _) => (None, Remainder::One(value)),
-            (_, Ordering::Less | Ordering::Equal) => (None, Remainder::One(value)),
-            _ => {
-                match (
-                    self.input_range.start.cmp(&value.start),
-                    self.input_range.end.cmp(&value.end),
-                ) {
-                    (Ordering::Greater, Ordering::Greater | Ordering::Equal) => (
-                        Some((self.input_range.start + self.diff)..(value.end + self.diff)),
-                        Remainder::One((value.start)..(self.input_range.start)),
-                    ),
-                    (Ordering::Greater, Ordering::Less) => (
-                        Some(
-                            (self.input_range.start + self.diff)
-                                ..(self.input_range.end + self.diff),
-                        ),
-                        Remainder::Two(
-                            (value.start)..(self.input_range.start),
-                            (self.input_range.end)..(value.end),
-                        ),
-                    ),
-                    (Ordering::Less | Ordering::Equal, Ordering::Less) => (
-                        Some((value.start + self.diff)..(self.input_range.end + self.diff)),
-                        Remainder::One((self.input_range.end)..(value.end)),
-                    ),
-                    (Ordering::Less | Ordering::Equal, Ordering::Greater | Ordering::Equal) => (
-                        Some((value.start + self.diff)..(value.end + self.diff)),
-                        Remainder::Zero,
-                    ),
-                }
-            }
-        }
+        Ok((from..(from + range), to - from))
     }
 }
 
-type Remainder = TwoIter<Range<i64>>;
-
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 enum Type {
     Seed,
@@ -242,6 +202,7 @@ impl FromStr for Type {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"seeds: 79 14 55 13
 
@@ -283,7 +244,37 @@ humidity-to-location map:
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 196167384);
+        let Some(input) = puzzle_input(5) else {
+            return;
+        };
+        assert_eq!(part1(&input), 196167384);
+    }
+    #[test]
+    fn malformed_input() {
+        let input = TEST_INPUT.replace("52 50 48", "52 5O 48");
+        let error = try_part1(&input).unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.text.as_str()),
+            (5, 4, "5O")
+        );
+    }
+    #[test]
+    fn seed_ranges() {
+        let input = TEST_INPUT.replace("79 14 55 13", "79 14 55");
+        assert_eq!(try_part1(&input), Ok(43));
+        assert_eq!(try_part2(&input), Err(SeedError::UnpairedSeeds));
+        let input = TEST_INPUT.replace("79 14 55 13", "5 0");
+        assert_eq!(try_part2(&input), Err(SeedError::NoSeeds));
+    }
+    #[test]
+    fn composed_maps() {
+        let pipeline = Day05::parse(TEST_INPUT).unwrap();
+        let locations: Vec<_> = [79, 14, 55, 13]
+            .map(|seed| pipeline.seed_to_location.apply(seed))
+            .into();
+        assert_eq!(locations, [82, 43, 86, 35]);
+        let error = try_part1("seeds: 1 2\n\nseed-to-soil map:\n50 98 2").unwrap_err();
+        assert_eq!(error.reason, "Maps don't lead from seed to location");
     }
     #[test]
     fn test_input_part2() {
@@ -291,6 +282,9 @@ humidity-to-location map:
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 125742456);
+        let Some(input) = puzzle_input(5) else {
+            return;
+        };
+        assert_eq!(part2(&input), 125742456);
     }
 }
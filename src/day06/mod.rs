@@ -1,40 +1,75 @@
-use std::str::FromStr;
+use crate::{
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
 
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day06;
+
+impl Solution for Day06 {
+    const DAY: u8 = 6;
+    type Parsed<'a> = (Races, Race);
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Races::parse(input)
+            .and_then(|races| Ok((races, Race::parse_part2(input)?)))
+            .map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1((races, _): &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(races.calculate().into())
+    }
+    fn part2((_, race): &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(race.ways_to_win().into())
+    }
+}
+
 pub fn part1(input: &str) -> u64 {
-    Races::parse(input).calculate()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> u64 {
-    Race::parse_part2(input).ways_to_win()
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<u64, ParseError> {
+    Ok(Races::parse(input)
+        .map_err(|error| error.locate(Day06::DAY, input))?
+        .calculate())
+}
+
+pub fn try_part2(input: &str) -> Result<u64, ParseError> {
+    Ok(Race::parse_part2(input)
+        .map_err(|error| error.locate(Day06::DAY, input))?
+        .ways_to_win())
 }
 
 #[derive(Debug)]
-struct Races {
+pub struct Races {
     races: Vec<Race>,
 }
 
 impl Races {
-    fn parse(input: &str) -> Self {
-        let (times, distances) = input.split_once('\n').unwrap();
-        let distance = distances
-            .strip_prefix("Distance: ")
-            .unwrap()
-            .split_whitespace()
-            .map(u64::from_str)
-            .map(Result::unwrap);
-        let races = times
-            .strip_prefix("Time: ")
-            .unwrap()
-            .split_whitespace()
-            .map(u64::from_str)
-            .map(Result::unwrap)
-            .zip(distance)
-            .map(Race::new)
-            .collect();
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let (times, distances) = input.split_once_or("\n", "Expected time and distance lines")?;
+        let distances = distances.strip_prefix_or("Distance: ", "Expected distances")?;
+        let times = times.strip_prefix_or("Time: ", "Expected times")?;
+        let distance = Self::parse_numbers(distances)?;
+        let time = Self::parse_numbers(times)?;
+        if time.len() != distance.len() {
+            return Err(distances.invalid("Expected a distance for every time"));
+        }
+        let races = time.into_iter().zip(distance).map(Race::new).collect();
+
+        Ok(Self { races })
+    }
 
-        Self { races }
+    fn parse_numbers(input: &str) -> ParseResult<'_, Vec<u64>> {
+        input
+            .split_whitespace()
+            .map(|number| number.parse_or("Couldn't parse number"))
+            .collect()
     }
 
     fn calculate(&self) -> u64 {
@@ -47,30 +82,27 @@ impl Races {
 }
 
 #[derive(Debug)]
-struct Race {
+pub struct Race {
     time: u64,
     record: u64,
 }
 
 impl Race {
-    fn parse_part2(input: &str) -> Self {
-        let (times, distances) = input.split_once('\n').unwrap();
-        let record: u64 = distances
-            .strip_prefix("Distance: ")
-            .unwrap()
-            .split_whitespace()
-            .collect::<String>()
-            .parse()
-            .unwrap();
-        let time: u64 = times
-            .strip_prefix("Time: ")
-            .unwrap()
+    fn parse_part2(input: &str) -> ParseResult<'_, Self> {
+        let (times, distances) = input.split_once_or("\n", "Expected time and distance lines")?;
+        let record =
+            Self::parse_kerned(distances.strip_prefix_or("Distance: ", "Expected distances")?)?;
+        let time = Self::parse_kerned(times.strip_prefix_or("Time: ", "Expected times")?)?;
+
+        Ok(Self { time, record })
+    }
+
+    fn parse_kerned(input: &str) -> ParseResult<'_, u64> {
+        input
             .split_whitespace()
             .collect::<String>()
             .parse()
-            .unwrap();
-
-        Self { time, record }
+            .map_err(|_| input.invalid("Couldn't parse number"))
     }
 
     fn new((time, record): (u64, u64)) -> Self {
@@ -114,6 +146,7 @@ impl StrictCeilFloor for f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"Time:      7  15   30
 Distance:  9  40  200"#;
@@ -124,7 +157,10 @@ Distance:  9  40  200"#;
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 449820);
+        let Some(input) = puzzle_input(6) else {
+            return;
+        };
+        assert_eq!(part1(&input), 449820);
     }
     #[test]
     fn test_input_part2() {
@@ -132,6 +168,9 @@ Distance:  9  40  200"#;
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 42250895);
+        let Some(input) = puzzle_input(6) else {
+            return;
+        };
+        assert_eq!(part2(&input), 42250895);
     }
 }
@@ -1,222 +1,364 @@
+use std::{collections::HashMap, iter};
+
+use crate::{
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day07;
+
+impl Solution for Day07 {
+    const DAY: u8 = 7;
+    type Parsed<'a> = Game;
+
+    // Both parts use the same deck and hand size
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Game::parse(input, &Ruleset::part1()).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(game: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(game.total_winnings(&Ruleset::part1()).into())
+    }
+    fn part2(game: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(game.total_winnings(&Ruleset::part2()).into())
+    }
+}
+
 pub fn part1(input: &str) -> u32 {
-    Game::<Part1Card>::parse(input).total_winnings()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> u32 {
-    Game::<Part2Card>::parse(input).total_winnings()
+    try_part2(input).unwrap()
 }
 
-#[derive(Debug)]
-struct Game<T: GameCard> {
-    hands: Vec<Hand<T>>,
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+    total_winnings(input, &Ruleset::part1())
 }
 
-impl<T: GameCard> Game<T> {
-    fn parse(input: &str) -> Self {
-        let hands = input.split('\n').map(Hand::parse).collect();
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+    total_winnings(input, &Ruleset::part2())
+}
+
+pub fn total_winnings(input: &str, rules: &Ruleset) -> Result<u32, ParseError> {
+    Ok(Game::parse(input, rules)
+        .map_err(|error| error.locate(Day07::DAY, input))?
+        .total_winnings(rules))
+}
 
-        Self { hands }
+// Explains the first hand in the game with the given cards
+pub fn explain(
+    input: &str,
+    cards: &str,
+    rules: &Ruleset,
+) -> Result<Option<Explanation>, ParseError> {
+    Ok(Game::parse(input, rules)
+        .map_err(|error| error.locate(Day07::DAY, input))?
+        .explain(cards, rules))
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    hands: Vec<Hand>,
+}
+
+impl Game {
+    fn parse<'a>(input: &'a str, rules: &Ruleset) -> ParseResult<'a, Self> {
+        let hands = input
+            .split('\n')
+            .map(|hand| Hand::parse(hand, rules))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { hands })
     }
 
-    fn total_winnings(&mut self) -> u32 {
-        self.hands.sort();
-        self.hands.iter().enumerate().fold(0, |acc, (index, hand)| {
-            acc + (hand.bid * (index as u32 + 1))
+    pub fn total_winnings(&self, rules: &Ruleset) -> u32 {
+        self.ranked(rules)
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (index, hand)| {
+                acc + (hand.bid * (index as u32 + 1))
+            })
+    }
+
+    pub fn explain(&self, cards: &str, rules: &Ruleset) -> Option<Explanation> {
+        let cards: Vec<_> = cards.chars().collect();
+        let (index, hand) = self
+            .ranked(rules)
+            .into_iter()
+            .enumerate()
+            .find(|(_, hand)| hand.cards == cards)?;
+        let substituted = rules.substitute(&cards);
+        let rank = index as u32 + 1;
+        Some(Explanation {
+            hand_type: HandType::classify(&substituted),
+            substitutions: cards
+                .iter()
+                .zip(substituted)
+                .enumerate()
+                .filter(|(_, (card, _))| rules.is_wildcard(**card))
+                .map(|(position, (_, substitute))| (position, substitute))
+                .collect(),
+            rank,
+            bid: hand.bid,
+            winnings: hand.bid * rank,
+            cards,
         })
     }
+
+    // Weakest first, keeping hands of equal strength in the order they were dealt
+    fn ranked(&self, rules: &Ruleset) -> Vec<&Hand> {
+        let mut hands: Vec<_> = self.hands.iter().collect();
+        hands.sort_by_cached_key(|hand| rules.strength(&hand.cards));
+        hands
+    }
 }
 
-trait GameCard: PartialEq + Eq + PartialOrd + Ord + TypedHand {
-    fn from_char(c: char) -> Self;
-    fn as_usize(&self) -> usize;
-}
-
-trait TypedHand: Sized {
-    fn hand_type(cards: &[Self; 5]) -> HandType;
-}
-
-macro_rules! impl_from_char {
-    ($type:ty, $j_name:ident) => {
-        impl GameCard for $type {
-            fn from_char(s: char) -> Self {
-                match s {
-                    'A' => Self::Ace,
-                    'K' => Self::King,
-                    'Q' => Self::Queen,
-                    'J' => Self::$j_name,
-                    'T' => Self::Ten,
-                    '9' => Self::Nine,
-                    '8' => Self::Eight,
-                    '7' => Self::Seven,
-                    '6' => Self::Six,
-                    '5' => Self::Five,
-                    '4' => Self::Four,
-                    '3' => Self::Three,
-                    '2' => Self::Two,
-                    _ => panic!("Unrecognized card"),
-                }
-            }
-            fn as_usize(&self) -> usize {
-                *self as usize
-            }
-        }
-    };
-}
-
-impl_from_char!(Part1Card, Jack);
-impl_from_char!(Part2Card, Joker);
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-#[repr(u8)]
-enum Part1Card {
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Jack,
-    Queen,
-    King,
-    Ace,
-}
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-#[repr(u8)]
-enum Part2Card {
-    Joker,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Queen,
-    King,
-    Ace,
-}
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-enum HandType {
-    HighCard,
-    OnePair,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub cards: Vec<char>,
+    pub hand_type: HandType,
+    // The position of each wildcard in the hand with the card it stands in for
+    pub substitutions: Vec<(usize, char)>,
+    // Starting from 1 for the weakest hand
+    pub rank: u32,
+    pub bid: u32,
+    pub winnings: u32,
 }
 
-impl From<&[u32]> for HandType {
-    fn from(counts: &[u32]) -> Self {
-        let mut hand_type = HandType::HighCard;
-
-        for count in counts {
-            match count {
-                5 => return HandType::FiveOfAKind,
-                4 => return HandType::FourOfAKind,
-                3 => match hand_type {
-                    HandType::OnePair => return HandType::FullHouse,
-                    _ => {
-                        hand_type = HandType::ThreeOfAKind;
-                    }
-                },
-                2 => match hand_type {
-                    HandType::ThreeOfAKind => return HandType::FullHouse,
-                    HandType::OnePair => return HandType::TwoPair,
-                    _ => {
-                        hand_type = HandType::OnePair;
-                    }
-                },
-                _ => continue,
-            }
-        }
-        hand_type
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    // Compare the cards in the order they were dealt
+    CardOrder,
+    // Compare the largest groups of cards first, then the highest cards, as in poker
+    PokerRank,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct Hand<T: GameCard> {
-    cards: [T; 5],
-    bid: u32,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ruleset {
+    // Cards from weakest to strongest
+    pub ranks: Vec<char>,
+    // Cards that stand in for whichever cards give the strongest hand
+    pub wildcards: Vec<char>,
+    pub hand_size: usize,
+    // Hand types from weakest to strongest, with any types left out weaker than all of them
+    pub type_order: Vec<HandType>,
+    pub tie_break: TieBreak,
 }
 
-impl<T: GameCard> Hand<T> {
-    fn parse(input: &str) -> Self {
-        let (cards, bid) = input.split_once(' ').unwrap();
-        let mut chars = cards.chars();
-        let cards = [
-            chars.next().map(T::from_char).unwrap(),
-            chars.next().map(T::from_char).unwrap(),
-            chars.next().map(T::from_char).unwrap(),
-            chars.next().map(T::from_char).unwrap(),
-            chars.next().map(T::from_char).unwrap(),
-        ];
+impl Ruleset {
+    pub fn part1() -> Self {
         Self {
-            cards,
-            bid: bid.parse().unwrap(),
+            ranks: "23456789TJQKA".chars().collect(),
+            wildcards: vec![],
+            hand_size: 5,
+            type_order: HandType::ALL.to_vec(),
+            tie_break: TieBreak::CardOrder,
+        }
+    }
+
+    pub fn part2() -> Self {
+        Self {
+            ranks: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            ..Self::part1()
+        }
+    }
+
+    pub fn rank(&self, card: char) -> Option<usize> {
+        self.ranks.iter().position(|rank| *rank == card)
+    }
+
+    pub fn is_wildcard(&self, card: char) -> bool {
+        self.wildcards.contains(&card)
+    }
+
+    pub fn hand_type(&self, cards: &[char]) -> HandType {
+        HandType::classify(&self.substitute(cards))
+    }
+
+    // The cards with each wildcard replaced to give the strongest hand type, preferring
+    // stronger hands under poker ranking where types tie, by searching over how the wildcards
+    // are shared between the cards they could become
+    pub fn substitute(&self, cards: &[char]) -> Vec<char> {
+        let wildcards = cards.iter().filter(|card| self.is_wildcard(**card)).count();
+        let counts = card_counts(cards);
+        let mut groups: Vec<_> = self
+            .ranks
+            .iter()
+            .rev()
+            .filter(|card| !self.is_wildcard(**card))
+            .enumerate()
+            .map(|(index, card)| (*card, counts.get(card).copied().unwrap_or(0), index))
+            // When bigger groups always make better hands, wildcards only improve the hand by
+            // joining cards already in it or the best card
+            .filter(|(_, count, index)| {
+                self.type_order != HandType::ALL || *count > 0 || *index == 0
+            })
+            .map(|(card, count, _)| (card, count))
+            .collect();
+        groups.sort_unstable();
+        if wildcards == 0 || groups.is_empty() {
+            return cards.to_vec();
         }
+
+        let best_shares = shares(groups.len(), wildcards)
+            .into_iter()
+            .max_by_key(|shares| {
+                let groups: Vec<_> = groups
+                    .iter()
+                    .zip(shares)
+                    .map(|((card, count), share)| (*card, count + share))
+                    .collect();
+                let hand_type = HandType::from_counts(groups.iter().map(|(_, count)| *count));
+                (self.type_strength(hand_type), self.poker_ranks(&groups))
+            })
+            .unwrap();
+        let mut substitutes = groups
+            .iter()
+            .zip(best_shares)
+            .flat_map(|((card, _), share)| iter::repeat_n(*card, share));
+        cards
+            .iter()
+            .map(|card| match self.is_wildcard(*card) {
+                true => substitutes.next().unwrap(),
+                false => *card,
+            })
+            .collect()
+    }
+
+    // Orders hands from weakest to strongest
+    fn strength(&self, cards: &[char]) -> (Option<usize>, Vec<Option<usize>>) {
+        let substituted = self.substitute(cards);
+        let tie_break = match self.tie_break {
+            TieBreak::CardOrder => cards.iter().map(|card| self.rank(*card)).collect(),
+            TieBreak::PokerRank => {
+                self.poker_ranks(&card_counts(&substituted).into_iter().collect::<Vec<_>>())
+            }
+        };
+        (
+            self.type_strength(HandType::classify(&substituted)),
+            tie_break,
+        )
+    }
+
+    fn type_strength(&self, hand_type: HandType) -> Option<usize> {
+        self.type_order.iter().position(|other| *other == hand_type)
+    }
+
+    // Ranks of every card, sorted by the size of its group and then by rank
+    fn poker_ranks(&self, groups: &[(char, usize)]) -> Vec<Option<usize>> {
+        let mut groups: Vec<_> = groups
+            .iter()
+            .map(|(card, count)| (*count, self.rank(*card)))
+            .collect();
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        groups
+            .into_iter()
+            .flat_map(|(count, rank)| iter::repeat_n(rank, count))
+            .collect()
     }
 }
 
-impl TypedHand for Part1Card {
-    fn hand_type(cards: &[Self; 5]) -> HandType {
-        let mut count = [0; 13];
-        cards.iter().for_each(|card| count[card.as_usize()] += 1);
-        count[..].into()
+// Every way of sharing `total` between the groups
+fn shares(groups: usize, total: usize) -> Vec<Vec<usize>> {
+    if groups == 1 {
+        return vec![vec![total]];
     }
+    (0..=total)
+        .flat_map(|share| {
+            shares(groups - 1, total - share)
+                .into_iter()
+                .map(move |mut rest| {
+                    rest.push(share);
+                    rest
+                })
+        })
+        .collect()
 }
 
-impl TypedHand for Part2Card {
-    fn hand_type(cards: &[Self; 5]) -> HandType {
-        let mut count = [0; 15];
-        cards.iter().for_each(|card| count[card.as_usize()] += 1);
-        let joker_count = count[0];
-        let hand_type = count[1..].into();
+fn card_counts(cards: &[char]) -> HashMap<char, usize> {
+    let mut counts = HashMap::new();
+    cards
+        .iter()
+        .for_each(|card| *counts.entry(*card).or_insert(0) += 1);
+    counts
+}
 
-        match (joker_count, hand_type) {
-            (5 | 4, _) => HandType::FiveOfAKind,
-            (3, HandType::OnePair) => HandType::FiveOfAKind,
-            (3, _) => HandType::FourOfAKind,
-            (2, HandType::ThreeOfAKind) => HandType::FiveOfAKind,
-            (2, HandType::OnePair) => HandType::FourOfAKind,
-            (2, _) => HandType::ThreeOfAKind,
-            (1, HandType::FourOfAKind) => HandType::FiveOfAKind,
-            (1, HandType::ThreeOfAKind) => HandType::FourOfAKind,
-            (1, HandType::TwoPair) => HandType::FullHouse,
-            (1, HandType::OnePair) => HandType::ThreeOfAKind,
-            (1, _) => HandType::OnePair,
-            (_, hand_type) => hand_type,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
+}
+
+impl HandType {
+    pub const ALL: [Self; 7] = [
+        Self::HighCard,
+        Self::OnePair,
+        Self::TwoPair,
+        Self::ThreeOfAKind,
+        Self::FullHouse,
+        Self::FourOfAKind,
+        Self::FiveOfAKind,
+    ];
+
+    // Classifies the cards as they are, without substituting wildcards
+    pub fn classify(cards: &[char]) -> Self {
+        Self::from_counts(card_counts(cards).into_values())
+    }
+
+    // Classifies a hand from how many there are of each card
+    pub fn from_counts(counts: impl IntoIterator<Item = usize>) -> Self {
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        match counts[..] {
+            [first, ..] if first >= 5 => Self::FiveOfAKind,
+            [4, ..] => Self::FourOfAKind,
+            [3, second, ..] if second >= 2 => Self::FullHouse,
+            [3, ..] => Self::ThreeOfAKind,
+            [2, 2, ..] => Self::TwoPair,
+            [2, ..] => Self::OnePair,
+            _ => Self::HighCard,
         }
     }
 }
 
-impl<T: GameCard> PartialOrd for Hand<T> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Hand {
+    cards: Vec<char>,
+    bid: u32,
 }
 
-impl<T: GameCard> Ord for Hand<T> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match T::hand_type(&self.cards).cmp(&T::hand_type(&other.cards)) {
-            std::cmp::Ordering::Equal => self.cards.cmp(&other.cards),
-            cmp => cmp,
+impl Hand {
+    fn parse<'a>(input: &'a str, rules: &Ruleset) -> ParseResult<'a, Self> {
+        let (cards, bid) = input.split_once_or(" ", "No space between cards and bid")?;
+        if let Some((index, _)) = cards
+            .char_indices()
+            .find(|(_, card)| rules.rank(*card).is_none())
+        {
+            return Err(cards.char_at(index).invalid("Unrecognized card"));
         }
+        if cards.chars().count() != rules.hand_size {
+            return Err(cards.invalid("Wrong number of cards"));
+        }
+        Ok(Self {
+            cards: cards.chars().collect(),
+            bid: bid.parse_or("Couldn't parse bid")?,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"32T3K 765
 T55J5 684
@@ -231,7 +373,18 @@ QQQJA 483"#;
 
     #[test]
     fn test_puzzle_input_part_1() {
-        assert_eq!(part1(PUZZLE_INPUT), 246795406);
+        let Some(input) = puzzle_input(7) else {
+            return;
+        };
+        assert_eq!(part1(&input), 246795406);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("32T3K 765\nT55X5 684").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 4, "X"));
+        let error = try_part2("32T3 765").unwrap_err();
+        assert_eq!(error.reason, "Wrong number of cards");
     }
 
     #[test]
@@ -239,8 +392,112 @@ QQQJA 483"#;
         assert_eq!(part2(TEST_INPUT), 5905);
     }
 
+    #[test]
+    fn hand_types() {
+        let cards = |hand: &str| hand.chars().collect::<Vec<_>>();
+        let (part1, part2) = (Ruleset::part1(), Ruleset::part2());
+        assert_eq!(part1.hand_type(&cards("KTJJT")), HandType::TwoPair);
+        assert_eq!(part2.hand_type(&cards("KTJJT")), HandType::FourOfAKind);
+        assert_eq!(part2.hand_type(&cards("JJJJJ")), HandType::FiveOfAKind);
+        assert_eq!(part2.hand_type(&cards("2345J")), HandType::OnePair);
+        assert_eq!(part2.substitute(&cards("KTJJT")), cards("KTTTT"));
+        assert_eq!(HandType::classify(&cards("AAAKKK")), HandType::FullHouse);
+    }
+    #[test]
+    fn explanations() {
+        let explanation = explain(TEST_INPUT, "KTJJT", &Ruleset::part2()).unwrap();
+        assert_eq!(
+            explanation,
+            Some(Explanation {
+                cards: "KTJJT".chars().collect(),
+                hand_type: HandType::FourOfAKind,
+                substitutions: vec![(2, 'T'), (3, 'T')],
+                rank: 5,
+                bid: 220,
+                winnings: 1100,
+            })
+        );
+        let explanation = explain(TEST_INPUT, "KTJJT", &Ruleset::part1()).unwrap();
+        assert_eq!(
+            explanation.map(|e| (e.hand_type, e.rank)),
+            Some((HandType::TwoPair, 2))
+        );
+        assert_eq!(explain(TEST_INPUT, "AAAAA", &Ruleset::part1()), Ok(None));
+
+        let input = "JJJJJ 5\nQQQQ2 10\n2JJJJ 1";
+        let explanation = explain(input, "JJJJJ", &Ruleset::part2()).unwrap().unwrap();
+        assert_eq!(explanation.hand_type, HandType::FiveOfAKind);
+        assert_eq!(
+            explanation.substitutions,
+            [(0, 'A'), (1, 'A'), (2, 'A'), (3, 'A'), (4, 'A')]
+        );
+        // Jokers are the weakest card, so JJJJJ loses to 2JJJJ
+        assert_eq!((explanation.rank, explanation.winnings), (2, 10));
+        let explanation = explain(input, "JJJJJ", &Ruleset::part1()).unwrap().unwrap();
+        assert!(explanation.substitutions.is_empty());
+        assert_eq!((explanation.rank, explanation.winnings), (3, 15));
+    }
+    #[test]
+    fn custom_type_order() {
+        // A high card beats a pair, so wildcards have to become cards not yet in the hand
+        let rules = Ruleset {
+            type_order: vec![HandType::OnePair, HandType::HighCard],
+            hand_size: 2,
+            ..Ruleset::part2()
+        };
+        let cards = |hand: &str| hand.chars().collect::<Vec<_>>();
+        assert_eq!(rules.substitute(&cards("AJ")), cards("AK"));
+        assert_eq!(rules.substitute(&cards("JJ")), cards("AK"));
+        assert_eq!(rules.hand_type(&cards("JJ")), HandType::HighCard);
+        assert_eq!(total_winnings("AJ 1\nQQ 10", &rules), Ok(12));
+    }
+    #[test]
+    fn custom_rules() {
+        // Two pair beats three of a kind, so a joker should make a second pair
+        let rules = Ruleset {
+            type_order: vec![
+                HandType::HighCard,
+                HandType::OnePair,
+                HandType::ThreeOfAKind,
+                HandType::TwoPair,
+            ],
+            ..Ruleset::part2()
+        };
+        assert_eq!(
+            rules.hand_type(&"KK2J3".chars().collect::<Vec<_>>()),
+            HandType::TwoPair
+        );
+        assert_eq!(total_winnings("KK2J3 1\nQQQ23 10", &rules), Ok(12));
+
+        let rules = Ruleset {
+            hand_size: 3,
+            tie_break: TieBreak::PokerRank,
+            ..Ruleset::part1()
+        };
+        // A pair of kings beats a pair of queens whatever the order of the cards
+        assert_eq!(total_winnings("2KK 1\nQQA 10", &rules), Ok(12));
+        let card_order = Ruleset {
+            tie_break: TieBreak::CardOrder,
+            ..rules.clone()
+        };
+        assert_eq!(total_winnings("2KK 1\nQQA 10", &card_order), Ok(21));
+        assert!(total_winnings("2KKK 1", &rules).is_err());
+
+        let rules = Ruleset {
+            hand_size: 10,
+            ..Ruleset::part2()
+        };
+        assert_eq!(total_winnings("JJJJJJJJJJ 1\nJJJJJ23456 2", &rules), Ok(5));
+        assert_eq!(
+            rules.substitute(&"JJJJJ23456".chars().collect::<Vec<_>>()),
+            "AAAAA23456".chars().collect::<Vec<_>>()
+        );
+    }
     #[test]
     fn test_puzzle_input_part_2() {
-        assert_eq!(part2(PUZZLE_INPUT), 249356515);
+        let Some(input) = puzzle_input(7) else {
+            return;
+        };
+        assert_eq!(part2(&input), 249356515);
     }
 }
@@ -1,89 +1,309 @@
-use num::Integer;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use crate::{
+    cycle::{chinese_remainder, Cycle},
+    graph::{self, Dot},
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day08;
+
+impl Solution for Day08 {
+    const DAY: u8 = 8;
+    type Parsed<'a> = Map<'a>;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Map::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(map: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        map.follow().map(Answer::from).map_err(SolveError::from)
+    }
+    fn part2(map: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        map.follow_part2()
+            .map(Answer::from)
+            .map_err(SolveError::from)
+    }
+}
+
 pub fn part1(input: &str) -> u32 {
-    Map::parse(input).follow()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> u128 {
-    Map::parse(input).follow_part2()
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+    Day08::parse(input)?.follow()
 }
 
-struct Map<'a> {
+pub fn try_part2(input: &str) -> Result<u128, ParseError> {
+    Day08::parse(input)?.follow_part2()
+}
+
+pub struct Map<'a> {
     directions: Directions<'a>,
     nodes: HashMap<&'a str, Node<'a>>,
+    // Kept to locate errors found while following the map
+    input: &'a str,
+    network: &'a str,
 }
 
 impl<'a> Map<'a> {
     const START_NODE: &'static str = "AAA";
     const END_NODE: &'static str = "ZZZ";
-    fn parse(input: &'a str) -> Self {
-        let (dir, nodes) = input.split_once("\n\n").unwrap();
+    fn parse(input: &'a str) -> ParseResult<'a, Self> {
+        let (dir, network) = input.split_once_or("\n\n", "No blank line after directions")?;
+        if dir.is_empty() {
+            return Err(dir.invalid("No directions"));
+        }
+        if let Some(index) = dir.find(|c| !matches!(c, 'L' | 'R')) {
+            return Err(dir.char_at(index).invalid("Unrecognised direction"));
+        }
+        let nodes: HashMap<_, _> = network
+            .split('\n')
+            .map(|node| Node::parse(node).map(|node| (node.name, node)))
+            .collect::<Result<_, _>>()?;
+        if let Some(child) = nodes
+            .values()
+            .flat_map(|node| [node.left, node.right])
+            .find(|child| !nodes.contains_key(child))
+        {
+            return Err(child.invalid("Unknown node"));
+        }
 
-        Self {
+        Ok(Self {
             directions: Directions {
                 directions: dir.as_bytes(),
                 position: 0,
             },
-            nodes: nodes
-                .split('\n')
-                .map(Node::parse)
-                .map(|node| (node.name, node))
-                .collect(),
-        }
+            nodes,
+            input,
+            network,
+        })
     }
 
-    fn follow(self) -> u32 {
-        let mut node = self.nodes.get(Self::START_NODE).unwrap();
+    fn follow(&self) -> Result<u32, ParseError> {
+        let locate = |text: &str, reason| text.invalid(reason).locate(Day08::DAY, self.input);
+        let start = self
+            .nodes
+            .get(Self::START_NODE)
+            .ok_or_else(|| locate(self.network, "No AAA node"))?;
+        if !self.nodes.contains_key(Self::END_NODE) {
+            return Err(locate(self.network, "No ZZZ node"));
+        }
 
-        for (i, direction) in self.directions.enumerate() {
-            node = self.nodes.get(node.child(direction)).unwrap();
+        let mut node = start;
+        let mut seen = HashSet::new();
+        for (i, direction) in self.directions.clone().enumerate() {
+            // Back on a node at the same point in the directions, so the path only repeats
+            if !seen.insert((node.name, i % self.directions.directions.len())) {
+                break;
+            }
+            node = &self.nodes[node.child(direction)];
             if node.name == Self::END_NODE {
-                return (i + 1) as u32;
+                return Ok((i + 1) as u32);
             }
         }
-        unreachable!()
+        Err(locate(start.name, "ZZZ can't be reached from AAA"))
     }
 
-    fn follow_part2(self) -> u128 {
-        let mut nodes: Vec<_> = self
-            .nodes
+    fn follow_part2(&self) -> Result<u128, ParseError> {
+        self.ghosts_meet().ok_or_else(|| {
+            self.network
+                .invalid("Ghosts never all reach end nodes together")
+                .locate(Day08::DAY, self.input)
+        })
+    }
+
+    // The first step at which every ghost is on an end node
+    pub fn ghosts_meet(&self) -> Option<u128> {
+        let ghosts = self.ghost_cycles();
+        // A hit before a ghost starts cycling is earlier than any step where all ghosts cycle
+        if let Some(step) = ghosts
             .iter()
-            .filter_map(|(_key, value)| {
-                if value.is_start_node() {
-                    Some(value)
-                } else {
-                    None
-                }
+            .flat_map(|ghost| &ghost.prefix_hits)
+            .copied()
+            .filter(|step| ghosts.iter().all(|ghost| ghost.is_end(*step)))
+            .min()
+        {
+            return Some(step as u128);
+        }
+
+        let start = ghosts.iter().map(|ghost| ghost.offset).max()? as i128;
+        let mut congruences = vec![(0, 1)];
+        for ghost in &ghosts {
+            congruences = congruences
+                .iter()
+                .flat_map(|congruence| {
+                    ghost.cycle_hits.iter().filter_map(|hit| {
+                        chinese_remainder(*congruence, (*hit as i128, ghost.period as i128))
+                    })
+                })
+                .collect();
+            congruences.sort_unstable();
+            congruences.dedup();
+        }
+        congruences
+            .into_iter()
+            .map(|(residue, modulus)| {
+                residue + ((start - residue).max(0) + modulus - 1) / modulus * modulus
             })
+            .min()
+            .map(|step| step as u128)
+    }
+
+    // One cycle per start node, in order of name
+    pub fn ghost_cycles(&self) -> Vec<GhostCycle> {
+        let mut starts: Vec<_> = self
+            .nodes
+            .values()
+            .filter(|node| node.is_start_node())
+            .map(|node| node.name)
             .collect();
-        let mut visited_z_nodes = vec![vec![]; nodes.len()];
-        let total_length = self.nodes.len() * self.directions.directions.len() + 1;
-        for (i, direction) in self.directions.enumerate().take(total_length) {
-            nodes = nodes
-                .into_iter()
-                .map(|node| self.nodes.get(node.child(direction)).unwrap())
-                .collect();
-            for (j, node) in nodes.iter().enumerate() {
-                if node.is_end_node() {
-                    visited_z_nodes[j].push((i + 1) as u128);
-                }
+        starts.sort_unstable();
+        starts
+            .into_iter()
+            .map(|start| self.ghost_cycle(start))
+            .collect()
+    }
+
+    // Follows the ghost until it is on a node it has been on at the same point in the directions
+    fn ghost_cycle(&self, start: &'a str) -> GhostCycle {
+        let directions = self.directions.directions;
+        let cycle = Cycle::detect((start, 0), |(name, index)| {
+            let child = self.nodes[name].child(directions[*index].into());
+            (child, (index + 1) % directions.len())
+        });
+        let (prefix_hits, cycle_hits) = (0..cycle.start + cycle.length)
+            .filter(|step| self.nodes[cycle.state(*step).0].is_end_node())
+            .partition(|step| *step < cycle.start);
+        GhostCycle {
+            offset: cycle.start,
+            period: cycle.length,
+            prefix_hits,
+            cycle_hits,
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new("network");
+        for name in self.node_names() {
+            let node = &self.nodes[name];
+            if node.is_start_node() {
+                dot.node(name, &[("shape", "doublecircle"), ("color", "green")]);
+            } else if node.is_end_node() {
+                dot.node(name, &[("shape", "doublecircle"), ("color", "red")]);
+            }
+            if node.left == node.right {
+                dot.edge(name, node.left, &[("label", "L/R")]);
+            } else {
+                dot.edge(name, node.left, &[("label", "L")]).edge(
+                    name,
+                    node.right,
+                    &[("label", "R")],
+                );
             }
         }
-        // We might be able to do this last bit more efficiently by checking for when cycles end
-        *visited_z_nodes
+        dot.to_string()
+    }
+
+    pub fn analyse(&self) -> Analysis<'a> {
+        let mut components =
+            graph::strongly_connected_components(self.node_names(), |name| self.children(name));
+        components
+            .iter_mut()
+            .for_each(|component| component.sort_unstable());
+        components.sort_unstable();
+
+        let starts: Vec<_> = self
+            .node_names()
             .into_iter()
-            .reduce(|acc, next| Self::lcms(&acc, &next))
-            .unwrap()
-            .iter()
-            .min()
-            .unwrap()
+            .filter(|name| self.nodes[name].is_start_node())
+            .collect();
+        let mut reachable_from_start = HashSet::new();
+        let reachable_ends = starts
+            .into_iter()
+            .map(|start| {
+                let reachable = self.reachable(start);
+                let mut ends: Vec<_> = reachable
+                    .iter()
+                    .copied()
+                    .filter(|name| self.nodes[name].is_end_node())
+                    .collect();
+                ends.sort_unstable();
+                reachable_from_start.extend(reachable);
+                (start, ends)
+            })
+            .collect();
+        let unreachable = self
+            .node_names()
+            .into_iter()
+            .filter(|name| !reachable_from_start.contains(name))
+            .collect();
+
+        Analysis {
+            components,
+            reachable_ends,
+            unreachable,
+        }
+    }
+
+    fn node_names(&self) -> Vec<&'a str> {
+        let mut names: Vec<_> = self.nodes.keys().copied().collect();
+        names.sort_unstable();
+        names
+    }
+
+    fn children(&self, name: &str) -> [&'a str; 2] {
+        let node = &self.nodes[name];
+        [node.left, node.right]
     }
 
-    fn lcms<T: Integer>(a: &[T], b: &[T]) -> Vec<T> {
-        a.iter().flat_map(|i| b.iter().map(|j| j.lcm(i))).collect()
+    // Every node reachable from the start, including the start itself
+    fn reachable(&self, start: &'a str) -> HashSet<&'a str> {
+        let mut reachable = HashSet::new();
+        let mut queue = vec![start];
+        while let Some(name) = queue.pop() {
+            if reachable.insert(name) {
+                queue.extend(self.children(name));
+            }
+        }
+        reachable
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Analysis<'a> {
+    pub components: Vec<Vec<&'a str>>,
+    // Each start node with the end nodes it can reach, whichever directions are followed
+    pub reachable_ends: Vec<(&'a str, Vec<&'a str>)>,
+    // Nodes that no start node can reach
+    pub unreachable: Vec<&'a str>,
+}
+
+// The steps at which a ghost is on an end node: hits before the path starts cycling at
+// `offset`, and hits within the first period which then repeat every `period` steps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhostCycle {
+    pub offset: usize,
+    pub period: usize,
+    pub prefix_hits: Vec<usize>,
+    pub cycle_hits: Vec<usize>,
+}
+
+impl GhostCycle {
+    pub fn is_end(&self, step: usize) -> bool {
+        if step < self.offset {
+            self.prefix_hits.contains(&step)
+        } else {
+            let step = self.offset + (step - self.offset) % self.period;
+            self.cycle_hits.contains(&step)
+        }
     }
 }
 
@@ -94,16 +314,13 @@ struct Node<'a> {
 }
 
 impl<'a> Node<'a> {
-    fn parse(input: &'a str) -> Self {
-        let (name, children) = input.split_once(" = ").unwrap();
+    fn parse(input: &'a str) -> ParseResult<'a, Self> {
+        let (name, children) = input.split_once_or(" = ", "No equals sign")?;
         let (left, right) = children
-            .strip_prefix('(')
-            .unwrap()
-            .strip_suffix(')')
-            .unwrap()
-            .split_once(", ")
-            .unwrap();
-        Self { name, left, right }
+            .strip_prefix_or("(", "Expected opening bracket")?
+            .strip_suffix_or(")", "Expected closing bracket")?
+            .split_once_or(", ", "Expected two children")?;
+        Ok(Self { name, left, right })
     }
 
     fn child(&self, direction: Direction) -> &'a str {
@@ -140,6 +357,7 @@ impl From<u8> for Direction {
 
 impl Direction {}
 
+#[derive(Clone)]
 struct Directions<'a> {
     directions: &'a [u8],
     position: usize,
@@ -158,6 +376,7 @@ impl<'a> Iterator for Directions<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT_1: &str = r#"RL
 
@@ -195,14 +414,115 @@ XXX = (XXX, XXX)"#;
     }
     #[test]
     fn test_puzzele_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 16579);
+        let Some(input) = puzzle_input(8) else {
+            return;
+        };
+        assert_eq!(part1(&input), 16579);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("LLX\n\nAAA = (AAA, AAA)").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (1, 3, "X"));
+        let error = try_part1("L\n\nAAA = (BBB, ZZZ)\nZZZ = (ZZZ, ZZZ)").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.reason),
+            (3, 8, "Unknown node")
+        );
+        let error = try_part1("L\n\nBBB = (BBB, BBB)").unwrap_err();
+        assert_eq!((error.line, error.reason), (3, "No AAA node"));
+        let error = try_part1("L\n\nAAA = (AAA, AAA)").unwrap_err();
+        assert_eq!((error.line, error.reason), (3, "No ZZZ node"));
+        let error =
+            try_part1("LR\n\nAAA = (BBB, AAA)\nBBB = (BBB, AAA)\nZZZ = (ZZZ, ZZZ)").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.reason),
+            (3, 1, "ZZZ can't be reached from AAA")
+        );
     }
     #[test]
     fn test_input_part2() {
         assert_eq!(part2(TEST_INPUT_PART_2), 6);
     }
     #[test]
+    fn ghost_cycles() {
+        let map = Day08::parse(TEST_INPUT_PART_2).unwrap();
+        let cycles = map.ghost_cycles();
+        assert_eq!(
+            cycles[0],
+            GhostCycle {
+                offset: 1,
+                period: 2,
+                prefix_hits: vec![],
+                cycle_hits: vec![2],
+            }
+        );
+        assert_eq!((cycles[1].offset, cycles[1].period), (1, 6));
+        assert_eq!(cycles[1].cycle_hits, [3, 6]);
+    }
+    #[test]
+    fn unaligned_cycles() {
+        // Hits at 2, 5, 8, ... and 3, 5, 7, ..., so the LCM of the first hits would be wrong
+        let map = Day08::parse(
+            "L\n\n1A = (1B, 1B)\n1B = (1Z, 1Z)\n1Z = (1C, 1C)\n1C = (1B, 1B)\n\
+             2A = (2B, 2B)\n2B = (2C, 2C)\n2C = (2Z, 2Z)\n2Z = (2C, 2C)",
+        )
+        .unwrap();
+        assert_eq!(map.ghosts_meet(), Some(5));
+        // Only hits at 1, and then at 1, 2, 3, ...
+        let map = Day08::parse(
+            "L\n\n1A = (1Z, 1Z)\n1Z = (1B, 1B)\n1B = (1B, 1B)\n2A = (2Z, 2Z)\n2Z = (2Z, 2Z)",
+        )
+        .unwrap();
+        assert_eq!(map.ghosts_meet(), Some(1));
+        // Only even steps, and only odd steps
+        let map = Day08::parse(
+            "L\n\n1A = (1Z, 1Z)\n1Z = (1A, 1A)\n2A = (2B, 2B)\n2B = (2Z, 2Z)\n2Z = (2B, 2B)",
+        )
+        .unwrap();
+        assert_eq!(map.ghosts_meet(), None);
+        let error = map.follow_part2().unwrap_err();
+        assert_eq!(
+            (error.line, error.reason),
+            (3, "Ghosts never all reach end nodes together")
+        );
+    }
+    #[test]
+    fn analysis() {
+        let map = Day08::parse(TEST_INPUT_PART_2).unwrap();
+        assert_eq!(
+            map.analyse(),
+            Analysis {
+                components: vec![
+                    vec!["11A"],
+                    vec!["11B", "11Z"],
+                    vec!["22A"],
+                    vec!["22B", "22C", "22Z"],
+                    vec!["XXX"],
+                ],
+                reachable_ends: vec![("11A", vec!["11Z"]), ("22A", vec!["22Z"])],
+                unreachable: vec![],
+            }
+        );
+        let map = Day08::parse(TEST_INPUT_1).unwrap();
+        let analysis = map.analyse();
+        assert_eq!(analysis.reachable_ends, [("AAA", vec!["ZZZ"])]);
+        assert!(analysis.unreachable.is_empty());
+        let map = Day08::parse("L\n\nAAA = (AAA, AAA)\nBBZ = (AAA, BBZ)").unwrap();
+        let analysis = map.analyse();
+        assert_eq!(analysis.reachable_ends, [("AAA", vec![])]);
+        assert_eq!(analysis.unreachable, ["BBZ"]);
+
+        let dot = map.to_dot();
+        assert!(dot.contains("\"AAA\" -> \"AAA\" [label=\"L/R\"];"));
+        assert!(dot.contains("\"BBZ\" -> \"AAA\" [label=\"L\"];"));
+        assert!(dot.contains("\"BBZ\" -> \"BBZ\" [label=\"R\"];"));
+        assert!(dot.contains("\"BBZ\" [shape=\"doublecircle\", color=\"red\"];"));
+    }
+    #[test]
     fn test_puzzele_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 12927600769609);
+        let Some(input) = puzzle_input(8) else {
+            return;
+        };
+        assert_eq!(part2(&input), 12927600769609);
     }
 }
@@ -1,24 +1,56 @@
-use std::str::FromStr;
+use crate::{
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
 
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day09;
+
+impl Solution for Day09 {
+    const DAY: u8 = 9;
+    type Parsed<'a> = Sequences;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Sequences::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(sequences: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(sequences.result_part1().into())
+    }
+    fn part2(sequences: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(sequences.result_part2().into())
+    }
+}
+
 pub fn part1(input: &str) -> isize {
-    Sequences::parse(input).result_part1()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> isize {
-    Sequences::parse(input).result_part2()
+    try_part2(input).unwrap()
 }
 
-struct Sequences {
+pub fn try_part1(input: &str) -> Result<isize, ParseError> {
+    Ok(Day09::parse(input)?.result_part1())
+}
+
+pub fn try_part2(input: &str) -> Result<isize, ParseError> {
+    Ok(Day09::parse(input)?.result_part2())
+}
+
+pub struct Sequences {
     sequences: Vec<Sequence>,
 }
 
 impl Sequences {
-    fn parse(input: &str) -> Self {
-        let sequences = input.split('\n').map(Sequence::parse).collect();
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let sequences = input
+            .split('\n')
+            .map(Sequence::parse)
+            .collect::<Result<_, _>>()?;
 
-        Self { sequences }
+        Ok(Self { sequences })
     }
 
     fn result_part1(&self) -> isize {
@@ -35,14 +67,16 @@ struct Sequence {
 }
 
 impl Sequence {
-    fn parse(input: &str) -> Self {
-        let seq = input
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let seq: Vec<_> = input
             .split_whitespace()
-            .map(FromStr::from_str)
-            .map(Result::unwrap)
-            .collect();
+            .map(|value| value.parse_or("Couldn't parse value"))
+            .collect::<Result<_, _>>()?;
+        if seq.is_empty() {
+            return Err(input.invalid("Empty sequence"));
+        }
 
-        Self { seq }
+        Ok(Self { seq })
     }
 
     fn next(&self) -> isize {
@@ -75,6 +109,7 @@ impl Sequence {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"0 3 6 9 12 15
 1 3 6 10 15 21
@@ -86,7 +121,15 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 1930746032);
+        let Some(input) = puzzle_input(9) else {
+            return;
+        };
+        assert_eq!(part1(&input), 1930746032);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("0 3 6\n1 3 b").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 5, "b"));
     }
     #[test]
     fn test_input_part2() {
@@ -94,6 +137,9 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 1154);
+        let Some(input) = puzzle_input(9) else {
+            return;
+        };
+        assert_eq!(part2(&input), 1154);
     }
 }
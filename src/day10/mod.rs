@@ -1,149 +1,271 @@
 use std::{
     collections::HashSet,
-    ops::{Index, IndexMut},
+    fmt,
+    io::{self, Write},
 };
 
+use crate::{
+    geometry::{Direction, Point, Position},
+    grid::Grid,
+    parse::{ParseError, ParseResult, ParseStr},
+    polygon::{Overflow, Polygon},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: u8 = 10;
+    type Parsed<'a> = Map;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Map::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(map: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        map.mid_point().map(Answer::from).map_err(SolveError::new)
+    }
+    fn part2(map: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        map.enclosed().map(Answer::from).map_err(SolveError::new)
+    }
+}
+
 pub fn part1(input: &str) -> usize {
-    Map::parse(input).mid_point()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    Map::parse(input).enclosed()
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<usize, LoopError> {
+    Day10::parse(input)?.mid_point()
 }
 
-struct Map {
-    map: Vec<Vec<Pipe>>,
+pub fn try_part2(input: &str) -> Result<usize, LoopError> {
+    Day10::parse(input)?.enclosed()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoopError {
+    Parse(ParseError),
+    NoLoop,
+    // The pipe at this position leads off the map or into a pipe it doesn't connect to
+    Broken(Position),
+    Overflow(Overflow),
+}
+
+impl From<ParseError> for LoopError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl From<Overflow> for LoopError {
+    fn from(error: Overflow) -> Self {
+        Self::Overflow(error)
+    }
+}
+
+impl fmt::Display for LoopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoopError::Parse(error) => error.fmt(f),
+            LoopError::NoLoop => write!(f, "Start tile doesn't connect to two pipes"),
+            LoopError::Broken(Position(x, y)) => {
+                write!(f, "Loop breaks at line {} column {}", y + 1, x + 1)
+            }
+            LoopError::Overflow(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LoopError {}
+
+#[derive(Clone)]
+pub struct Map {
+    map: Grid<Pipe>,
 }
 
 impl Map {
-    fn parse(input: &str) -> Self {
-        Self {
-            map: input
-                .split('\n')
-                .map(|line| line.chars().map(Pipe::parse).collect())
-                .collect(),
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let map = Grid::parse(input, Pipe::parse, "Unexpected pipe")?;
+        if map.find(|pipe| pipe == &Pipe::Start).is_none() {
+            return Err(input.invalid("No start tile"));
         }
+
+        Ok(Self { map })
     }
 
-    fn mid_point(&self) -> usize {
+    fn mid_point(&self) -> Result<usize, LoopError> {
         let mut count = 0;
-        self.walk_path(|_| count += 1);
-        count / 2
+        self.walk_path(|_| count += 1)?;
+        Ok(count / 2)
     }
 
-    fn walk_path<F>(&self, mut on_section: F)
-    where
-        F: FnMut(Position),
-    {
-        let (mut position, mut direction) = self.initial_position_and_direction();
-        // Move off start
-        position = position.move_next(direction);
-        on_section(position);
-        let mut pipe = self[position];
-        direction = pipe.outgoing_direction(direction);
-
+    fn walk_path(&self, mut on_section: impl FnMut(Position)) -> Result<(), LoopError> {
+        let (mut direction, _) = self.start_directions()?;
+        let mut position = self.start();
         loop {
-            position = position.move_next(direction);
-            pipe = self[position];
+            position = self
+                .map
+                .step(position, direction)
+                .ok_or(LoopError::Broken(position))?;
             on_section(position);
+            let pipe = self.map[position];
             if pipe == Pipe::Start {
-                break;
+                return Ok(());
             }
-            direction = pipe.outgoing_direction(direction);
+            direction = pipe
+                .outgoing_direction(direction)
+                .ok_or(LoopError::Broken(position))?;
         }
     }
 
     fn start(&self) -> Position {
-        self.iter()
-            .find(|(_, pipe)| pipe == &Pipe::Start)
-            .unwrap()
-            .0
+        self.map.find(|pipe| pipe == &Pipe::Start).unwrap()
     }
 
-    fn iter(&self) -> impl Iterator<Item = (Position, Pipe)> + '_ {
-        self.map.iter().enumerate().flat_map(|(y, row)| {
-            row.iter()
-                .enumerate()
-                .map(move |(x, pipe)| (Position(x, y), *pipe))
-        })
+    // The first two directions from the start to pipes that connect back to it
+    fn start_directions(&self) -> Result<(Direction, Direction), LoopError> {
+        let position = self.start();
+        let directions: Vec<_> = self
+            .map
+            .neighbours(position)
+            .filter(|(direction, next)| self.map[*next].connects(direction.reverse()))
+            .map(|(direction, _)| direction)
+            .collect();
+        match directions[..] {
+            [first, second, ..] => Ok((first, second)),
+            _ => Err(LoopError::NoLoop),
+        }
     }
 
-    fn initial_position_and_direction(&self) -> (Position, Direction) {
-        let position = self.start();
-        let width = self.width();
+    // The map with the start tile replaced by the pipe it must be
+    fn pipes(&self) -> Result<Grid<Pipe>, LoopError> {
+        let (first, second) = self.start_directions()?;
+        let mut pipes = self.map.clone();
+        pipes[self.start()] = Pipe::connecting(first, second);
+        Ok(pipes)
+    }
 
-        for direction in Direction::ALL {
-            if position.can_move(direction, width)
-                && self[position.move_next(direction)].connects(direction.opposite())
-            {
-                return (position, direction);
+    pub fn classify(&self) -> Result<Grid<Tile>, LoopError> {
+        let mut path = HashSet::new();
+        self.walk_path(|position| {
+            path.insert(position);
+        })?;
+        let pipes = self.pipes()?;
+        let mut tiles = Grid::filled(pipes.width(), pipes.height(), Tile::Outside);
+        // A tile is enclosed if the loop crosses the column above it an odd number of times
+        for x in 0..pipes.width() {
+            let mut crossings = 0;
+            for y in 0..pipes.height() {
+                let position = Position(x, y);
+                if path.contains(&position) {
+                    tiles[position] = Tile::Loop;
+                    crossings += pipes[position].west_count();
+                } else if crossings % 2 == 1 {
+                    tiles[position] = Tile::Enclosed;
+                }
             }
         }
-
-        (position, Direction::South)
+        Ok(tiles)
     }
 
-    fn width(&self) -> usize {
-        self.map.first().unwrap().len()
+    // The centres of the loop's tiles are the lattice points on its boundary, so the
+    // enclosed tiles are the interior points
+    fn enclosed(&self) -> Result<usize, LoopError> {
+        let mut vertices = vec![];
+        self.walk_path(|position| vertices.push(Point::from(position)))?;
+        Ok(Polygon::new(vertices).interior_points()?)
     }
 
-    fn start_tile_pipe(&self) -> Pipe {
-        let position = self.start();
-        let width = self.width();
-        let mut first_direction = None;
-
-        for direction in Direction::ALL {
-            if position.can_move(direction, width)
-                && self[position.move_next(direction)].connects(direction.opposite())
-            {
-                match first_direction {
-                    Some(first_direction) => return Pipe::connecting(first_direction, direction),
-                    None => first_direction = Some(direction),
-                }
+    // Loop tiles are drawn with box-drawing characters, enclosed tiles as I and outside as O
+    pub fn render(&self) -> Result<String, LoopError> {
+        let mut rendered = self.pipes()?.map(Pipe::box_drawing);
+        for (position, tile) in self.classify()?.iter() {
+            match tile {
+                Tile::Loop => {}
+                Tile::Enclosed => rendered[position] = 'I',
+                Tile::Outside => rendered[position] = 'O',
             }
         }
-        unreachable!();
+        Ok(rendered.to_string())
     }
 
-    fn enclosed(mut self) -> usize {
-        let mut path = HashSet::new();
-        let mut enclosed = 0;
-        self.walk_path(|position| {
-            path.insert(position);
-        });
+    // Every tile keeps its pipe, coloured by whether it is the start, on the loop,
+    // enclosed or outside
+    pub fn render_coloured(&self) -> Result<String, LoopError> {
+        const RESET: &str = "\x1b[0m";
+        let tiles = self.classify()?;
         let start = self.start();
-        self[start] = self.start_tile_pipe();
-        for (position @ Position(x, y), _) in self.iter() {
-            if !path.contains(&position) {
-                let is_enclosed = (0..y)
-                    .map(|i| Position(x, i))
-                    .filter(|pos| path.contains(pos))
-                    .fold(0, |acc, pos| acc + self[pos].west_count())
-                    % 2
-                    == 1;
-                if is_enclosed {
-                    enclosed += 1;
-                }
+        let mut rendered = String::new();
+        for (position, pipe) in self.pipes()?.iter() {
+            if position.0 == 0 && position.1 > 0 {
+                rendered.push('\n');
             }
+            let colour = match tiles[position] {
+                _ if position == start => "\x1b[1;31m",
+                Tile::Loop => "\x1b[1;33m",
+                Tile::Enclosed => "\x1b[30;42m",
+                Tile::Outside => "\x1b[2;34m",
+            };
+            rendered.push_str(colour);
+            rendered.push(pipe.box_drawing());
+            rendered.push_str(RESET);
         }
-
-        enclosed
+        Ok(rendered)
     }
-}
-
-impl Index<Position> for Map {
-    type Output = Pipe;
 
-    fn index(&self, Position(x, y): Position) -> &Self::Output {
-        &self.map[y][x]
+    // A binary PPM image with each tile drawn as a 3x3 block of squares `scale` pixels wide,
+    // failing with `InvalidData` if the start isn't on a loop
+    pub fn write_ppm(&self, mut out: impl Write, scale: usize) -> io::Result<()> {
+        let invalid = |error| io::Error::new(io::ErrorKind::InvalidData, error);
+        let tiles = self.classify().map_err(invalid)?;
+        let pipes = self.pipes().map_err(invalid)?;
+        let start = self.start();
+        let tile_size = 3 * scale;
+        let (width, height) = (pipes.width() * tile_size, pipes.height() * tile_size);
+        write!(out, "P6\n{width} {height}\n255\n")?;
+
+        let mut row = Vec::with_capacity(width * 3);
+        for py in 0..height {
+            row.clear();
+            for px in 0..width {
+                let position = Position(px / tile_size, py / tile_size);
+                let pipe = pipes[position];
+                let on_pipe = match (px / scale % 3, py / scale % 3) {
+                    (1, 1) => pipe != Pipe::None,
+                    (1, 0) => pipe.connects(Direction::North),
+                    (2, 1) => pipe.connects(Direction::East),
+                    (1, 2) => pipe.connects(Direction::South),
+                    (0, 1) => pipe.connects(Direction::West),
+                    _ => false,
+                };
+                let colour = match (tiles[position], on_pipe) {
+                    (_, true) if position == start => [255, 60, 60],
+                    (Tile::Loop, true) => [255, 200, 0],
+                    (Tile::Loop, false) => [40, 40, 40],
+                    (Tile::Enclosed, true) => [120, 220, 140],
+                    (Tile::Enclosed, false) => [40, 160, 60],
+                    (Tile::Outside, true) => [70, 80, 130],
+                    (Tile::Outside, false) => [20, 30, 80],
+                };
+                row.extend_from_slice(&colour);
+            }
+            out.write_all(&row)?;
+        }
+        Ok(())
     }
 }
-impl IndexMut<Position> for Map {
-    fn index_mut(&mut self, Position(x, y): Position) -> &mut Self::Output {
-        &mut self.map[y][x]
-    }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Loop,
+    Enclosed,
+    Outside,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -159,8 +281,8 @@ enum Pipe {
 }
 
 impl Pipe {
-    fn parse(c: char) -> Self {
-        match c {
+    fn parse(c: char) -> Option<Self> {
+        Some(match c {
             '|' => Self::NorthSouth,
             '-' => Self::EastWest,
             'L' => Self::NorthEast,
@@ -169,7 +291,20 @@ impl Pipe {
             'F' => Self::SouthEast,
             '.' => Self::None,
             'S' => Self::Start,
-            _ => panic!(),
+            _ => return None,
+        })
+    }
+
+    fn box_drawing(&self) -> char {
+        match self {
+            Pipe::None => '·',
+            Pipe::NorthSouth => '│',
+            Pipe::NorthEast => '└',
+            Pipe::NorthWest => '┘',
+            Pipe::EastWest => '─',
+            Pipe::SouthEast => '┌',
+            Pipe::SouthWest => '┐',
+            Pipe::Start => 'S',
         }
     }
 
@@ -197,8 +332,8 @@ impl Pipe {
         }
     }
 
-    fn outgoing_direction(&self, incoming_direction: Direction) -> Direction {
-        match (self, incoming_direction) {
+    fn outgoing_direction(&self, incoming_direction: Direction) -> Option<Direction> {
+        Some(match (self, incoming_direction) {
             (Pipe::NorthSouth, Direction::North) => Direction::North,
             (Pipe::NorthSouth, Direction::South) => Direction::South,
             (Pipe::NorthEast, Direction::South) => Direction::East,
@@ -211,8 +346,8 @@ impl Pipe {
             (Pipe::SouthEast, Direction::West) => Direction::South,
             (Pipe::SouthWest, Direction::North) => Direction::West,
             (Pipe::SouthWest, Direction::East) => Direction::South,
-            _ => panic!("Unexpected direction {incoming_direction:?} with pipe {self:?}"),
-        }
+            _ => return None,
+        })
     }
 
     fn connects(&self, direction: Direction) -> bool {
@@ -241,57 +376,10 @@ impl Pipe {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Direction {
-    North,
-    South,
-    East,
-    West,
-}
-
-impl Direction {
-    const ALL: [Self; 4] = [
-        Direction::North,
-        Direction::South,
-        Direction::East,
-        Direction::West,
-    ];
-    fn opposite(&self) -> Self {
-        match self {
-            Direction::North => Direction::South,
-            Direction::South => Direction::North,
-            Direction::East => Direction::West,
-            Direction::West => Direction::East,
-        }
-    }
-}
-
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
-struct Position(usize, usize);
-
-impl Position {
-    fn move_next(&self, direction: Direction) -> Self {
-        match direction {
-            Direction::North => Position(self.0, self.1 - 1),
-            Direction::South => Position(self.0, self.1 + 1),
-            Direction::East => Position(self.0 + 1, self.1),
-            Direction::West => Position(self.0 - 1, self.1),
-        }
-    }
-
-    fn can_move(&self, direction: Direction, max_len: usize) -> bool {
-        match direction {
-            Direction::North => self.1 > 0,
-            Direction::South => self.1 < max_len - 1,
-            Direction::East => self.0 < max_len - 1,
-            Direction::West => self.0 > 0,
-        }
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"7-F7-
 .FJ|7
@@ -337,7 +425,36 @@ L7JLJL-JLJLJL--JLJ.L"#;
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 6757);
+        let Some(input) = puzzle_input(10) else {
+            return;
+        };
+        assert_eq!(part1(&input), 6757);
+    }
+    #[test]
+    fn malformed_input() {
+        let Err(LoopError::Parse(error)) = try_part1("S-7\n|.|\nL*J") else {
+            panic!("Expected a parse error");
+        };
+        assert_eq!((error.line, error.column, error.text.as_str()), (3, 2, "*"));
+        let Err(LoopError::Parse(error)) = try_part2("F-7\n|.|\nL-J") else {
+            panic!("Expected a parse error");
+        };
+        assert_eq!(error.reason, "No start tile");
+    }
+    #[test]
+    fn broken_loop() {
+        assert_eq!(try_part1("S"), Err(LoopError::NoLoop));
+        assert_eq!(
+            try_part1("S-7\n|.|\nL-|"),
+            Err(LoopError::Broken(Position(2, 2)))
+        );
+        assert_eq!(
+            try_part2("S-7\n|.-\nL-J"),
+            Err(LoopError::Broken(Position(2, 1)))
+        );
+        let map = Day10::parse("S").unwrap();
+        assert_eq!(map.render(), Err(LoopError::NoLoop));
+        assert!(map.write_ppm(vec![], 1).is_err());
     }
     #[test]
     fn test_input_part2() {
@@ -356,7 +473,43 @@ L7JLJL-JLJLJL--JLJ.L"#;
         assert_eq!(part2(TEST_INPUT_PART2_3), 10);
     }
     #[test]
+    fn render() {
+        let map = Day10::parse(TEST_INPUT_PART2).unwrap();
+        assert_eq!(
+            map.render().unwrap(),
+            "OOOOOOOOOOO
+O┌───────┐O
+O│┌─────┐│O
+O││OOOOO││O
+O││OOOOO││O
+O│└─┐O┌─┘│O
+O│II│O│II│O
+O└──┘O└──┘O
+OOOOOOOOOOO"
+        );
+        assert_eq!(
+            map.render().unwrap().matches('I').count(),
+            map.enclosed().unwrap()
+        );
+        let coloured = map.render_coloured().unwrap();
+        assert!(coloured.starts_with("\x1b[2;34m·\x1b[0m"));
+        assert!(coloured.contains("\x1b[1;31m┌\x1b[0m"));
+        assert_eq!(coloured.matches("\x1b[30;42m").count(), 4);
+    }
+    #[test]
+    fn write_ppm() {
+        let map = Day10::parse(TEST_INPUT).unwrap();
+        let mut image = vec![];
+        map.write_ppm(&mut image, 2).unwrap();
+        let header = "P6\n30 30\n255\n";
+        assert!(image.starts_with(header.as_bytes()));
+        assert_eq!(image.len(), header.len() + 30 * 30 * 3);
+    }
+    #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 523);
+        let Some(input) = puzzle_input(10) else {
+            return;
+        };
+        assert_eq!(part2(&input), 523);
     }
 }
@@ -1,64 +1,82 @@
+use crate::{
+    geometry::Position,
+    grid::Grid,
+    parse::{ParseError, ParseResult},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day11;
+
+impl Solution for Day11 {
+    const DAY: u8 = 11;
+    type Parsed<'a> = (StartMap<2>, StartMap<1_000_000>);
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        StartMap::parse(input)
+            .and_then(|part1| Ok((part1, StartMap::parse(input)?)))
+            .map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1((map, _): &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(map.total_length().into())
+    }
+    fn part2((_, map): &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(map.total_length().into())
+    }
+}
+
 pub fn calculate<const EXPANSION_FACTOR: usize>(input: &str) -> usize {
-    StartMap::<EXPANSION_FACTOR>::parse(input).total_length()
+    try_calculate::<EXPANSION_FACTOR>(input).unwrap()
+}
+
+pub fn try_calculate<const EXPANSION_FACTOR: usize>(input: &str) -> Result<usize, ParseError> {
+    Ok(StartMap::<EXPANSION_FACTOR>::parse(input)
+        .map_err(|error| error.locate(Day11::DAY, input))?
+        .total_length())
 }
 
 #[derive(Debug)]
-struct StartMap<const N: usize = 1> {
+pub struct StartMap<const N: usize = 1> {
     galaxies: Vec<Position>,
 }
 
 impl<const EXPANSION_FACTOR: usize> StartMap<EXPANSION_FACTOR> {
-    fn parse(input: &str) -> Self {
-        let mut galaxies: Vec<_> = input
-            .split('\n')
-            .enumerate()
-            .flat_map(|(row_index, row)| {
-                row.chars()
-                    .enumerate()
-                    .filter_map(move |(column_index, val)| {
-                        if matches!(val, '#') {
-                            Some(Position(row_index, column_index))
-                        } else {
-                            None
-                        }
-                    })
-            })
-            .collect();
-
-        Self::expand_galaxies(&mut galaxies);
-
-        Self { galaxies }
-    }
-
-    fn expand_galaxies(galaxies: &mut [Position]) {
-        let (horizontal_galaxies, vertical_galaxies) = galaxies.iter().fold(
-            (Vec::new(), Vec::new()),
-            |(mut horizontal_galaxies, mut vertical_galaxies), Position(x, y)| {
-                horizontal_galaxies.set(*x, true);
-                vertical_galaxies.set(*y, true);
-                (horizontal_galaxies, vertical_galaxies)
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let grid = Grid::parse(
+            input,
+            |c| match c {
+                '#' => Some(true),
+                '.' => Some(false),
+                _ => None,
             },
+            "Unexpected symbol in input",
+        )?;
+        let column_expansions = Self::to_expansions(
+            grid.columns()
+                .map(|mut column| column.any(|galaxy| *galaxy)),
         );
-        let horizontal_scaling = Self::to_expansions(horizontal_galaxies);
-        let vertical_scaling = Self::to_expansions(vertical_galaxies);
-        galaxies.iter_mut().for_each(|pos| {
-            pos.0 += horizontal_scaling[pos.0];
-            pos.1 += vertical_scaling[pos.1];
-        });
+        let row_expansions = Self::to_expansions(grid.rows().map(|row| row.contains(&true)));
+        let galaxies = grid
+            .iter()
+            .filter(|(_, galaxy)| **galaxy)
+            .map(|(Position(x, y), _)| Position(x + column_expansions[x], y + row_expansions[y]))
+            .collect();
+
+        Ok(Self { galaxies })
     }
 
-    fn to_expansions(galaxy_indexes: Vec<bool>) -> Vec<usize> {
-        let mut expansions = vec![0; galaxy_indexes.len()];
+    fn to_expansions(contains_galaxy: impl Iterator<Item = bool>) -> Vec<usize> {
         let mut expansion = 0;
-        for (index, contains_galaxy) in galaxy_indexes.iter().enumerate() {
-            if !contains_galaxy {
-                expansion += EXPANSION_FACTOR - 1;
-            }
-            expansions[index] = expansion;
-        }
-        expansions
+        contains_galaxy
+            .map(|contains_galaxy| {
+                if !contains_galaxy {
+                    expansion += EXPANSION_FACTOR - 1;
+                }
+                expansion
+            })
+            .collect()
     }
 
     fn total_length(&self) -> usize {
@@ -67,7 +85,7 @@ impl<const EXPANSION_FACTOR: usize> StartMap<EXPANSION_FACTOR> {
             .map(|pos| {
                 self.galaxies
                     .iter()
-                    .map(|pos2| pos.distance_from(pos2))
+                    .map(|pos2| pos.manhattan(*pos2))
                     .sum::<usize>()
             })
             .sum::<usize>()
@@ -75,34 +93,10 @@ impl<const EXPANSION_FACTOR: usize> StartMap<EXPANSION_FACTOR> {
     }
 }
 
-trait VecSet<T> {
-    fn set(&mut self, index: usize, value: T);
-}
-
-impl<T> VecSet<T> for Vec<T>
-where
-    T: Default,
-{
-    fn set(&mut self, index: usize, value: T) {
-        if self.len() <= index {
-            self.resize_with(index + 1, Default::default);
-        }
-        self[index] = value;
-    }
-}
-
-#[derive(Debug)]
-struct Position(usize, usize);
-
-impl Position {
-    fn distance_from(&self, other: &Self) -> usize {
-        self.0.abs_diff(other.0) + self.1.abs_diff(other.1)
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"...#......
 .......#..
@@ -121,7 +115,15 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(calculate::<2>(PUZZLE_INPUT), 9947476);
+        let Some(input) = puzzle_input(11) else {
+            return;
+        };
+        assert_eq!(calculate::<2>(&input), 9947476);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_calculate::<2>("#..\n.?.").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 2, "?"));
     }
     #[test]
     fn test_input_part2() {
@@ -130,6 +132,9 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(calculate::<1000000>(PUZZLE_INPUT), 519939907614);
+        let Some(input) = puzzle_input(11) else {
+            return;
+        };
+        assert_eq!(calculate::<1000000>(&input), 519939907614);
     }
 }
@@ -1,28 +1,69 @@
-use std::str::FromStr;
+use crate::{
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
 
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day12;
+
+impl Solution for Day12 {
+    const DAY: u8 = 12;
+    type Parsed<'a> = SpringRecord;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        SpringRecord::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(record: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(record.possible_configurations().into())
+    }
+    fn part2(record: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(record.unfolded_configurations().into())
+    }
+}
+
 pub fn part1(input: &str) -> usize {
-    SpringRecord::parse(input).possible_configurations()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    todo!();
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+    Ok(Day12::parse(input)?.possible_configurations())
+}
+
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+    Ok(Day12::parse(input)?.unfolded_configurations())
 }
 
 #[derive(Debug)]
-struct SpringRecord {
+pub struct SpringRecord {
     records: Vec<Record>,
 }
 
 impl SpringRecord {
-    fn parse(input: &str) -> Self {
-        let records = input.split('\n').map(Record::parse).collect();
-        Self { records }
+    const UNFOLD: usize = 5;
+
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let records = input
+            .split('\n')
+            .map(Record::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { records })
     }
 
     fn possible_configurations(&self) -> usize {
-        self.records.iter().map(Record::all_possible_matches).sum()
+        self.records.iter().map(Record::possible_matches).sum()
+    }
+
+    fn unfolded_configurations(&self) -> usize {
+        self.records
+            .iter()
+            .map(|record| record.unfold(Self::UNFOLD).possible_matches())
+            .sum()
     }
 }
 
@@ -33,142 +74,100 @@ struct Record {
 }
 
 impl Record {
-    fn parse(input: &str) -> Self {
-        let (springs, groups) = input.split_once(' ').unwrap();
-
-        Self {
-            springs: springs.chars().map(SpringStatus::from_char).collect(),
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let (springs, groups) = input.split_once_or(" ", "No space between springs and groups")?;
+
+        Ok(Self {
+            springs: springs
+                .char_indices()
+                .map(|(index, c)| {
+                    SpringStatus::from_char(c)
+                        .ok_or(springs.char_at(index).invalid("Unexpected spring status"))
+                })
+                .collect::<Result<_, _>>()?,
             damaged_groups: groups
                 .split(',')
-                .map(FromStr::from_str)
-                .map(Result::unwrap)
-                .collect(),
-        }
+                .map(|group| group.parse_or("Couldn't parse group"))
+                .collect::<Result<_, _>>()?,
+        })
     }
 
-    fn all_possible_matches(&self) -> usize {
-        let mut option = self.springs.clone();
-        let unknown_indexes = self.unknown_indexes();
-
-        (0..(1 << unknown_indexes.len()))
-            .filter(|combination| {
-                unknown_indexes
-                    .iter()
-                    .enumerate()
-                    .for_each(|(offset, index)| {
-                        option[*index] =
-                            SpringStatus::from_bit(((1 << offset) & combination) >> offset)
-                    });
-                Self::matches(&option, &self.damaged_groups)
-            })
-            .count()
-    }
-    fn possible_matches(&self) -> usize {
-        for range in self.broken_unknown_ranges() {
-            for i in 0..self.damaged_groups.len() {}
+    fn unfold(&self, times: usize) -> Self {
+        let mut springs = Vec::with_capacity((self.springs.len() + 1) * times);
+        for copy in 0..times {
+            if copy > 0 {
+                springs.push(SpringStatus::Unknown);
+            }
+            springs.extend_from_slice(&self.springs);
         }
 
-        0
-    }
-    fn matches_for_broken_unknown_range(
-        springs: &[SpringStatus],
-        damaged_groups: &[usize],
-    ) -> Option<usize> {
-        None
-    }
-    fn broken_unknown_ranges(&self) -> impl Iterator<Item = &[SpringStatus]> {
-        self.springs
-            .split(|status| status.is_operational())
-            .filter(|slice| slice.is_empty())
-    }
-    fn next_broken_range(springs: &[SpringStatus]) -> &[SpringStatus] {
-        let mut skipped = 0;
-        let len = springs
-            .iter()
-            .skip_while(|status| {
-                skipped += 1;
-                status.is_operational()
-            })
-            .take_while(|status| status.not_operational())
-            .count();
-        &springs[skipped..(skipped + len)]
+        Self {
+            springs,
+            damaged_groups: self.damaged_groups.repeat(times),
+        }
     }
 
-    fn unknown_indexes(&self) -> Vec<usize> {
-        self.springs
-            .iter()
-            .enumerate()
-            .filter_map(|(index, status)| match status {
-                SpringStatus::Unknown => Some(index),
-                _ => None,
-            })
-            .collect()
-    }
-
-    fn matches(springs: &[SpringStatus], damaged_groups: &[usize]) -> bool {
-        let mut expected_damaged_groups = damaged_groups.iter();
-        let mut current_damage_group = None;
-        for spring in springs {
-            match spring {
-                SpringStatus::Operational => {
-                    if current_damage_group.is_some()
-                        && current_damage_group.as_ref() != expected_damaged_groups.next()
+    fn possible_matches(&self) -> usize {
+        let springs = &self.springs;
+        let groups = &self.damaged_groups;
+        // matches[i][g] counts the arrangements of springs[i..] containing groups[g..]
+        let mut matches = vec![vec![0; groups.len() + 1]; springs.len() + 2];
+        matches[springs.len()][groups.len()] = 1;
+        matches[springs.len() + 1][groups.len()] = 1;
+
+        for i in (0..springs.len()).rev() {
+            for g in 0..=groups.len() {
+                let mut count = 0;
+                if springs[i].could_be_operational() {
+                    count += matches[i + 1][g];
+                }
+                if springs[i].could_be_damaged() && g < groups.len() {
+                    let end = i + groups[g];
+                    if end <= springs.len()
+                        && springs[i..end].iter().all(SpringStatus::could_be_damaged)
+                        && springs
+                            .get(end)
+                            .is_none_or(SpringStatus::could_be_operational)
                     {
-                        return false;
+                        count += matches[end + 1][g + 1];
                     }
-                    current_damage_group = None;
-                }
-                SpringStatus::Damaged => {
-                    current_damage_group = Some(current_damage_group.unwrap_or(0) + 1)
                 }
-                SpringStatus::Unknown => panic!("Shouldn't call matches with unknown springs"),
+                matches[i][g] = count;
             }
         }
-        expected_damaged_groups.next() == current_damage_group.as_ref()
-            && expected_damaged_groups.next().is_none()
+        matches[0][0]
     }
 }
 
-fn to_unsigned(springs: &[SpringStatus]) -> usize {
-    springs.iter().enumerate().fold(0, |acc, (index, status)| {
-        acc + ((*status as usize) << index)
-    })
-}
-
 #[derive(Debug, Clone, Copy)]
 enum SpringStatus {
-    Unknown = 0,
-    Damaged = 1,
+    Unknown,
+    Damaged,
     Operational,
 }
 
 impl SpringStatus {
-    fn from_char(c: char) -> Self {
+    fn from_char(c: char) -> Option<Self> {
         match c {
-            '.' => Self::Operational,
-            '#' => Self::Damaged,
-            _ => Self::Unknown,
+            '.' => Some(Self::Operational),
+            '#' => Some(Self::Damaged),
+            '?' => Some(Self::Unknown),
+            _ => None,
         }
     }
 
-    fn from_bit(bit: usize) -> Self {
-        match bit {
-            0 => Self::Operational,
-            1 => Self::Damaged,
-            _ => panic!("Should only be called with 0 and 1"),
-        }
-    }
-    fn not_operational(&self) -> bool {
+    fn could_be_damaged(&self) -> bool {
         !matches!(self, Self::Operational)
     }
-    fn is_operational(&self) -> bool {
-        matches!(self, Self::Operational)
+    fn could_be_operational(&self) -> bool {
+        !matches!(self, Self::Damaged)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"???.### 1,1,3
 .??..??...?##. 1,1,3
@@ -183,14 +182,33 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 7047);
+        let Some(input) = puzzle_input(12) else {
+            return;
+        };
+        assert_eq!(part1(&input), 7047);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("???.### 1,1,3\n.??..??...?##. 1,x,3").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.text.as_str()),
+            (2, 18, "x")
+        );
     }
     #[test]
     fn test_input_part2() {
         assert_eq!(part2(TEST_INPUT), 525152);
     }
-    // #[test]
-    // fn test_puzzle_input_part2() {
-    //     assert_eq!(part2(PUZZLE_INPUT), 0);
-    // }
+    #[test]
+    fn test_puzzle_input_part2() {
+        let Some(input) = puzzle_input(12) else {
+            return;
+        };
+        assert_eq!(part2(&input), 17391848518844);
+    }
+    #[test]
+    fn many_unknowns() {
+        let input = format!("{} 1,1,1", "?".repeat(120));
+        assert_eq!(part1(&input), 266_916);
+    }
 }
@@ -1,32 +1,100 @@
+use std::fmt;
+
+use crate::{
+    grid::Grid,
+    parse::{ParseError, ParseResult},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day13;
+
+impl Solution for Day13 {
+    const DAY: u8 = 13;
+    type Parsed<'a> = Map;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Map::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(map: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        map.summary::<Part1>()
+            .map(Answer::from)
+            .map_err(SolveError::new)
+    }
+    fn part2(map: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        map.summary::<Part2>()
+            .map(Answer::from)
+            .map_err(SolveError::new)
+    }
+}
+
 pub fn part1(input: &str) -> usize {
-    Map::parse(input).summary::<Part1>()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    Map::parse(input).summary::<Part2>()
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<usize, ReflectionError> {
+    Day13::parse(input)?.summary::<Part1>()
+}
+
+pub fn try_part2(input: &str) -> Result<usize, ReflectionError> {
+    Day13::parse(input)?.summary::<Part2>()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReflectionError {
+    Parse(ParseError),
+    // 1-based index of the pattern
+    NoReflection(usize),
+}
+
+impl From<ParseError> for ReflectionError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
 }
 
-struct Map {
+impl fmt::Display for ReflectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReflectionError::Parse(error) => error.fmt(f),
+            ReflectionError::NoReflection(pattern) => {
+                write!(f, "No reflection found in pattern {pattern}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReflectionError {}
+
+pub struct Map {
     sections: Vec<MapSection>,
 }
 
 impl Map {
-    fn parse(input: &str) -> Self {
-        let sections = input.split("\n\n").map(MapSection::parse).collect();
-        Self { sections }
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let sections = input
+            .split("\n\n")
+            .map(MapSection::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { sections })
     }
 
-    fn summary<T: ReflectionTest>(&self) -> usize {
+    fn summary<T: ReflectionTest>(&self) -> Result<usize, ReflectionError> {
         self.sections
             .iter()
-            .map(|section| {
+            .enumerate()
+            .map(|(i, section)| {
                 section
                     .row_reflection::<T>()
                     .map(|r| r * 100)
                     .or_else(|| section.column_reflection::<T>())
-                    .expect("No reflection found")
+                    .ok_or(ReflectionError::NoReflection(i + 1))
             })
             .sum()
     }
@@ -38,26 +106,23 @@ struct MapSection {
 }
 
 impl MapSection {
-    fn parse(input: &str) -> Self {
-        let mut columns = vec![];
-        let mut rows = vec![];
-        for (index, line) in input.split('\n').enumerate() {
-            if index == 0 {
-                columns.resize(line.len(), 0);
-            }
-            let mut row = 0;
-            for (i, c) in line.chars().enumerate() {
-                let val = match c {
-                    '.' => 0,
-                    '#' => 1,
-                    _ => panic!("Unexpected symbol in input"),
-                };
-                row += val << i;
-                columns[i] += val << index;
-            }
-            rows.push(row);
-        }
-        Self { rows, columns }
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let grid = Grid::parse(
+            input,
+            |c| match c {
+                '.' => Some(0),
+                '#' => Some(1),
+                _ => None,
+            },
+            "Unexpected symbol in input",
+        )?;
+        let rows = grid.rows().map(|row| Self::to_bits(row.iter())).collect();
+        let columns = grid.columns().map(Self::to_bits).collect();
+        Ok(Self { rows, columns })
+    }
+
+    fn to_bits<'a>(line: impl Iterator<Item = &'a usize>) -> usize {
+        line.enumerate().fold(0, |bits, (i, val)| bits + (val << i))
     }
 
     fn row_reflection<T: ReflectionTest>(&self) -> Option<usize> {
@@ -131,6 +196,7 @@ impl ReflectionTest for Part2 {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"#.##..##.
 ..#.##.#.
@@ -154,7 +220,29 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 34889);
+        let Some(input) = puzzle_input(13) else {
+            return;
+        };
+        assert_eq!(part1(&input), 34889);
+    }
+    #[test]
+    fn malformed_input() {
+        let Err(ReflectionError::Parse(error)) = try_part1("#.#\n#.") else {
+            panic!("Expected a parse error");
+        };
+        assert_eq!((error.line, error.column), (2, 1));
+        let Err(ReflectionError::Parse(error)) = try_part2("#.#\n#o#") else {
+            panic!("Expected a parse error");
+        };
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 2, "o"));
+    }
+    #[test]
+    fn no_reflection() {
+        assert_eq!(try_part1("#.#\n##."), Err(ReflectionError::NoReflection(1)));
+        assert_eq!(
+            try_part2("##\n##\n\n#.#\n##."),
+            Err(ReflectionError::NoReflection(1))
+        );
     }
     #[test]
     fn test_input_part2() {
@@ -162,6 +250,9 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 34224);
+        let Some(input) = puzzle_input(13) else {
+            return;
+        };
+        assert_eq!(part2(&input), 34224);
     }
 }
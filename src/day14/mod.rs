@@ -1,40 +1,72 @@
-use std::collections::HashMap;
-
+use crate::{
+    cycle,
+    geometry::Position,
+    grid::Grid,
+    parse::{ParseError, ParseResult},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day14;
+
+impl Solution for Day14 {
+    const DAY: u8 = 14;
+    type Parsed<'a> = Platform;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Platform::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(platform: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(platform.total().into())
+    }
+    fn part2(platform: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(platform.run(Platform::SPIN_CYCLES).into())
+    }
+}
+
 pub fn part1(input: &str) -> usize {
-    Platform::parse(input).total()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    Platform::parse(input).run()
+    try_part2(input).unwrap()
 }
 
-struct Platform {
-    rocks: Vec<Vec<Rock>>,
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+    Ok(Day14::parse(input)?.total())
+}
+
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+    Ok(Day14::parse(input)?.run(Platform::SPIN_CYCLES))
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Platform {
+    rocks: Grid<Rock>,
 }
 
 impl Platform {
-    fn parse(input: &str) -> Self {
-        let rocks = input
-            .split('\n')
-            .map(|line| line.chars().map(Rock::from_char).collect::<Vec<_>>())
-            .collect();
+    const SPIN_CYCLES: usize = 1_000_000_000;
 
-        Self { rocks }
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let rocks = Grid::parse(input, Rock::from_char, "Unexpected rock")?;
+        Ok(Self { rocks })
     }
 
     fn total(&self) -> usize {
+        let height = self.rocks.height();
         let mut total = 0;
-        for column in 0..self.width() {
-            let mut weight = self.rocks.len();
-            for (row, rock) in self.column_iter(column).enumerate() {
+        for column in self.rocks.columns() {
+            let mut weight = height;
+            for (row, rock) in column.enumerate() {
                 match rock {
                     Rock::Round => {
                         total += weight;
                         weight -= 1;
                     }
-                    Rock::Square => weight = self.rocks.len() - row - 1,
+                    Rock::Square => weight = height - row - 1,
                     Rock::None => {}
                 }
             }
@@ -43,120 +75,32 @@ impl Platform {
         total
     }
 
-    fn run(&mut self) -> usize {
-        let mut history = HashMap::new();
-        let mut cycle_length: isize = -1;
-        let mut cycle = Vec::new();
-        for i in 0..1000000 {
-            self.cycle();
-            if i < 10 {
-                continue;
-            }
-            let value = self.total_north_load();
-            let value_history = history.entry(value).or_default();
-            match value_history {
-                (last_seen, None) => *value_history = (i, Some(i - *last_seen)),
-                (last_seen, Some(diff)) => {
-                    let new_diff = i - *last_seen;
-                    if new_diff == *diff || cycle_length > 0 {
-                        if new_diff <= cycle_length {
-                            cycle.push(value);
-                        } else {
-                            cycle_length = new_diff;
-                            cycle.clear();
-                        }
-                    }
-                    *value_history = (i, Some(new_diff));
-                }
-            }
-            if cycle.len() == cycle_length as usize {
-                return cycle[(999999999 - i as usize - 1) % cycle_length as usize];
-            }
-        }
-        0
-    }
-
-    fn cycle(&mut self) {
-        self.tilt_north();
-        self.tilt_west();
-        self.tilt_south();
-        self.tilt_east();
+    fn run(&self, spin_cycles: usize) -> usize {
+        cycle::iterate(self.clone(), spin_cycles, Self::cycle).total_north_load()
     }
 
-    fn tilt_north(&mut self) {
-        for column in 0..self.width() {
-            let mut position = 0;
-            for row in 0..self.rocks.len() {
-                let rock = self.rocks[row][column];
-                match rock {
-                    Rock::Round => {
-                        if position != row {
-                            self.rocks[position][column] = Rock::Round;
-                            self.rocks[row][column] = Rock::None;
-                        }
-                        position += 1;
-                    }
-                    Rock::Square => position = row + 1,
-                    Rock::None => {}
-                }
-            }
+    fn cycle(&self) -> Self {
+        let mut platform = self.clone();
+        for _ in 0..4 {
+            platform.tilt_north();
+            platform.rocks = platform.rocks.rotate_clockwise();
         }
+        platform
     }
 
-    fn tilt_south(&mut self) {
-        for column in 0..self.width() {
-            let mut position = self.rocks.len() - 1;
-            for row in (0..self.rocks.len()).rev() {
-                let rock = self.rocks[row][column];
-                match rock {
-                    Rock::Round => {
-                        if position != row {
-                            self.rocks[position][column] = Rock::Round;
-                            self.rocks[row][column] = Rock::None;
-                        }
-                        position = position.saturating_sub(1);
-                    }
-                    Rock::Square => position = row.saturating_sub(1),
-                    Rock::None => {}
-                }
-            }
-        }
-    }
-
-    fn tilt_west(&mut self) {
-        for row in 0..self.rocks.len() {
-            let mut position = 0;
-            for column in 0..self.width() {
-                let rock = self.rocks[row][column];
-                match rock {
-                    Rock::Round => {
-                        if position != column {
-                            self.rocks[row][position] = Rock::Round;
-                            self.rocks[row][column] = Rock::None;
-                        }
-                        position += 1;
-                    }
-                    Rock::Square => position = column + 1,
-                    Rock::None => {}
-                }
-            }
-        }
-    }
-
-    fn tilt_east(&mut self) {
-        for row in 0..self.rocks.len() {
-            let mut position = self.width() - 1;
-            for column in (0..self.width()).rev() {
-                let rock = self.rocks[row][column];
-                match rock {
+    fn tilt_north(&mut self) {
+        for x in 0..self.rocks.width() {
+            let mut free = 0;
+            for y in 0..self.rocks.height() {
+                match self.rocks[Position(x, y)] {
                     Rock::Round => {
-                        if position != column {
-                            self.rocks[row][position] = Rock::Round;
-                            self.rocks[row][column] = Rock::None;
+                        if free != y {
+                            self.rocks[Position(x, free)] = Rock::Round;
+                            self.rocks[Position(x, y)] = Rock::None;
                         }
-                        position = position.saturating_sub(1);
+                        free += 1;
                     }
-                    Rock::Square => position = column.saturating_sub(1),
+                    Rock::Square => free = y + 1,
                     Rock::None => {}
                 }
             }
@@ -164,29 +108,21 @@ impl Platform {
     }
 
     fn total_north_load(&self) -> usize {
-        let len = self.rocks.len();
+        let height = self.rocks.height();
         self.rocks
-            .iter()
+            .rows()
             .enumerate()
             .map(|(index, row)| {
                 row.iter()
                     .filter(|rock| matches!(rock, Rock::Round))
                     .count()
-                    * (len - index)
+                    * (height - index)
             })
             .sum()
     }
-
-    fn width(&self) -> usize {
-        self.rocks.first().unwrap().len()
-    }
-
-    fn column_iter(&self, index: usize) -> impl Iterator<Item = Rock> + '_ {
-        self.rocks.iter().map(move |row| row[index])
-    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 enum Rock {
     Round,
     Square,
@@ -194,11 +130,12 @@ enum Rock {
 }
 
 impl Rock {
-    fn from_char(c: char) -> Self {
+    fn from_char(c: char) -> Option<Self> {
         match c {
-            'O' => Self::Round,
-            '#' => Self::Square,
-            _ => Self::None,
+            'O' => Some(Self::Round),
+            '#' => Some(Self::Square),
+            '.' => Some(Self::None),
+            _ => None,
         }
     }
 }
@@ -206,6 +143,7 @@ impl Rock {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"O....#....
 O.OO#....#
@@ -224,14 +162,32 @@ O.#..O.#.#
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 111979);
+        let Some(input) = puzzle_input(14) else {
+            return;
+        };
+        assert_eq!(part1(&input), 111979);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("O..\n.o#").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 2, "o"));
     }
     #[test]
     fn test_input_part2() {
         assert_eq!(part2(TEST_INPUT), 64);
     }
     #[test]
+    fn few_spin_cycles() {
+        let platform = Day14::parse(TEST_INPUT).unwrap();
+        assert_eq!(platform.run(1), 87);
+        assert_eq!(platform.run(2), 69);
+        assert_eq!(platform.run(3), 69);
+    }
+    #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 102055);
+        let Some(input) = puzzle_input(14) else {
+            return;
+        };
+        assert_eq!(part2(&input), 102055);
     }
 }
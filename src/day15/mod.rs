@@ -1,10 +1,56 @@
-use std::hash::Hasher;
+use std::{
+    hash::{BuildHasher, Hasher},
+    mem,
+};
 
+use crate::{
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day15;
+
+impl Solution for Day15 {
+    const DAY: u8 = 15;
+    type Parsed<'a> = (Vec<&'a str>, Vec<Instruction<'a>>);
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        let steps: Vec<_> = input.split(',').collect();
+        let instructions = steps
+            .iter()
+            .copied()
+            .map(Instruction::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|error| error.locate(Self::DAY, input))?;
+        Ok((steps, instructions))
+    }
+    fn part1((steps, _): &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(hash_total(steps).into())
+    }
+    fn part2((_, instructions): &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(focusing_power(instructions).into())
+    }
+}
+
 pub fn part1(input: &str) -> usize {
-    input
-        .split(',')
+    hash_total(&input.split(',').collect::<Vec<_>>())
+}
+
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
+}
+
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+    let (_, instructions) = Day15::parse(input)?;
+    Ok(focusing_power(&instructions))
+}
+
+fn hash_total(steps: &[&str]) -> usize {
+    steps
+        .iter()
         .map(|seq| {
             let mut hasher = SimpleHasher::default();
             hasher.write(seq.as_bytes());
@@ -13,110 +59,244 @@ pub fn part1(input: &str) -> usize {
         .sum()
 }
 
-pub fn part2(input: &str) -> usize {
-    let instructions = input.split(',').map(Instruction::parse);
-    let mut lens_array = LensArray::new();
-    lens_array.apply_instructions(instructions);
-    lens_array.power()
+fn focusing_power(instructions: &[Instruction]) -> usize {
+    instructions
+        .iter()
+        .copied()
+        .collect::<LensArray<_, _>>()
+        .focusing_power()
+}
+
+// An insertion-ordered hash map that keeps its entries in the puzzle's 256 boxes. Keys are
+// hashed from their bytes alone so the boxes match the HASH algorithm, where `Hash` for
+// strings would also hash a terminator.
+#[derive(Debug, Clone)]
+pub struct LensArray<K, V, S = BuildSimpleHasher> {
+    // Entries in insertion order, with removed entries left empty until compacted
+    entries: Vec<Option<(K, V)>>,
+    // Indices into `entries` for each box, in the order the lenses sit in the box
+    boxes: Vec<Vec<usize>>,
+    len: usize,
+    hasher: S,
+}
+
+impl<K, V> LensArray<K, V> {
+    pub fn new() -> Self {
+        Self::with_hasher(BuildSimpleHasher)
+    }
 }
 
-struct LensArray<'a> {
-    boxes: Vec<LensBox<'a>>,
+impl<K, V> Default for LensArray<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<'a> LensArray<'a> {
-    fn new() -> Self {
+impl<K, V, S> LensArray<K, V, S> {
+    const BOXES: usize = 256;
+
+    pub fn with_hasher(hasher: S) -> Self {
         Self {
-            boxes: vec![Default::default(); 256],
+            entries: vec![],
+            boxes: vec![vec![]; Self::BOXES],
+            len: 0,
+            hasher,
         }
     }
-    fn apply_instructions(&mut self, instructions: impl Iterator<Item = Instruction<'a>>) {
-        instructions.for_each(|instructions| {
-            self.boxes[instructions.label_hash()].apply_instruction(instructions)
-        })
+
+    pub fn len(&self) -> usize {
+        self.len
     }
 
-    fn power(&self) -> usize {
-        self.boxes
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries
             .iter()
-            .enumerate()
-            .map(|(index, lens_box)| (index + 1) * lens_box.power())
-            .sum()
+            .flatten()
+            .map(|(key, value)| (key, value))
     }
-}
 
-enum Instruction<'a> {
-    Remove { label: &'a str },
-    Add { label: &'a str, focal_length: u8 },
+    pub fn boxes(&self) -> impl Iterator<Item = impl Iterator<Item = (&K, &V)>> {
+        self.boxes.iter().map(|indices| {
+            indices.iter().map(|index| {
+                let (key, value) = self.entries[*index].as_ref().unwrap();
+                (key, value)
+            })
+        })
+    }
+
+    fn compact(&mut self) {
+        let mut new_indices = vec![0; self.entries.len()];
+        let mut entries = Vec::with_capacity(self.len);
+        for (index, entry) in self.entries.drain(..).enumerate() {
+            if entry.is_some() {
+                new_indices[index] = entries.len();
+                entries.push(entry);
+            }
+        }
+        self.entries = entries;
+        self.boxes
+            .iter_mut()
+            .flatten()
+            .for_each(|index| *index = new_indices[*index]);
+    }
 }
 
-impl<'a> Instruction<'a> {
-    fn parse(input: &'a str) -> Self {
-        match input.split_once('-') {
-            Some((label, _)) => Self::Remove { label },
-            None => match input.split_once('=') {
-                Some((label, focal_length)) => Self::Add {
-                    label,
-                    focal_length: focal_length.parse().unwrap(),
-                },
-                None => panic!("Failed to parse instruction"),
-            },
+impl<K, V, S> LensArray<K, V, S>
+where
+    K: AsRef<[u8]> + Eq,
+    S: BuildHasher,
+{
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let lens_box = self.box_index(key.as_ref());
+        match self.find(lens_box, key.as_ref()) {
+            Some(slot) => {
+                let index = self.boxes[lens_box][slot];
+                let (_, old) = self.entries[index].as_mut().unwrap();
+                Some(mem::replace(old, value))
+            }
+            None => {
+                self.boxes[lens_box].push(self.entries.len());
+                self.entries.push(Some((key, value)));
+                self.len += 1;
+                None
+            }
         }
     }
 
-    fn label(&self) -> &'a str {
-        match self {
-            Instruction::Remove { label } => label,
-            Instruction::Add { label, .. } => label,
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        Q: AsRef<[u8]> + ?Sized,
+    {
+        let lens_box = self.box_index(key.as_ref());
+        let slot = self.find(lens_box, key.as_ref())?;
+        let index = self.boxes[lens_box].remove(slot);
+        let (_, value) = self.entries[index].take()?;
+        self.len -= 1;
+        if self.entries.len() > 2 * self.len + Self::BOXES {
+            self.compact();
         }
+        Some(value)
     }
 
-    fn label_hash(&self) -> usize {
-        let mut hasher = SimpleHasher::default();
-        hasher.write(self.label().as_bytes());
-        hasher.finish() as usize
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: AsRef<[u8]> + ?Sized,
+    {
+        let lens_box = self.box_index(key.as_ref());
+        let index = self.boxes[lens_box][self.find(lens_box, key.as_ref())?];
+        self.entries[index].as_ref().map(|(_, value)| value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: AsRef<[u8]> + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    fn box_index(&self, key: &[u8]) -> usize {
+        let mut hasher = self.hasher.build_hasher();
+        hasher.write(key);
+        hasher.finish() as usize % Self::BOXES
+    }
+
+    fn find(&self, lens_box: usize, key: &[u8]) -> Option<usize> {
+        self.boxes[lens_box].iter().position(
+            |index| matches!(&self.entries[*index], Some((other, _)) if other.as_ref() == key),
+        )
     }
 }
 
-#[derive(Default, Clone)]
-struct LensBox<'a> {
-    lenses: Vec<Lens<'a>>,
+impl<K, S> LensArray<K, u8, S> {
+    pub fn focusing_power(&self) -> usize {
+        self.boxes()
+            .enumerate()
+            .map(|(index, lenses)| {
+                (index + 1)
+                    * lenses
+                        .enumerate()
+                        .map(|(slot, (_, focal_length))| (slot + 1) * *focal_length as usize)
+                        .sum::<usize>()
+            })
+            .sum()
+    }
 }
 
-impl<'a> LensBox<'a> {
-    fn apply_instruction(&mut self, instruction: Instruction<'a>) {
+impl<'a, S: BuildHasher> LensArray<&'a str, u8, S> {
+    pub fn apply(&mut self, instruction: Instruction<'a>) {
         match instruction {
-            Instruction::Remove { label } => self.lenses.retain(|lens| lens.label != label),
+            Instruction::Remove { label } => {
+                self.remove(label);
+            }
             Instruction::Add {
                 label,
                 focal_length,
-            } => match self.lenses.iter_mut().find(|lens| lens.label == label) {
-                Some(lens) => lens.focal_length = focal_length,
-                None => self.lenses.push(Lens {
-                    label,
-                    focal_length,
-                }),
-            },
+            } => {
+                self.insert(label, focal_length);
+            }
         }
     }
+}
 
-    fn power(&self) -> usize {
-        self.lenses
-            .iter()
-            .enumerate()
-            .map(|(index, lens)| (index + 1) * lens.focal_length as usize)
-            .sum()
+impl<'a, S: BuildHasher> Extend<Instruction<'a>> for LensArray<&'a str, u8, S> {
+    fn extend<I: IntoIterator<Item = Instruction<'a>>>(&mut self, iter: I) {
+        iter.into_iter()
+            .for_each(|instruction| self.apply(instruction));
+    }
+}
+
+impl<'a, S: BuildHasher + Default> FromIterator<Instruction<'a>> for LensArray<&'a str, u8, S> {
+    fn from_iter<I: IntoIterator<Item = Instruction<'a>>>(iter: I) -> Self {
+        let mut lens_array = Self::with_hasher(S::default());
+        lens_array.extend(iter);
+        lens_array
     }
 }
 
-#[derive(Clone)]
-struct Lens<'a> {
-    label: &'a str,
-    focal_length: u8,
+impl<K: AsRef<[u8]> + Eq, V, S: BuildHasher> Extend<(K, V)> for LensArray<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|(key, value)| {
+            self.insert(key, value);
+        });
+    }
 }
 
-#[derive(Default)]
-struct SimpleHasher {
+impl<K: AsRef<[u8]> + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for LensArray<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut lens_array = Self::with_hasher(S::default());
+        lens_array.extend(iter);
+        lens_array
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    Remove { label: &'a str },
+    Add { label: &'a str, focal_length: u8 },
+}
+
+impl<'a> Instruction<'a> {
+    fn parse(input: &'a str) -> ParseResult<'a, Self> {
+        match input.split_once('-') {
+            Some((label, _)) => Ok(Self::Remove { label }),
+            None => {
+                let (label, focal_length) =
+                    input.split_once_or("=", "Failed to parse instruction")?;
+                Ok(Self::Add {
+                    label,
+                    focal_length: focal_length.parse_or("Invalid focal length")?,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SimpleHasher {
     value: u64,
 }
 
@@ -132,9 +312,21 @@ impl Hasher for SimpleHasher {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BuildSimpleHasher;
+
+impl BuildHasher for BuildSimpleHasher {
+    type Hasher = SimpleHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        SimpleHasher::default()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7"#;
 
@@ -144,7 +336,61 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 507291);
+        let Some(input) = puzzle_input(15) else {
+            return;
+        };
+        assert_eq!(part1(&input), 507291);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part2("rn=1,cm,qp=3").unwrap_err();
+        assert_eq!((error.column, error.text.as_str()), (6, "cm"));
+        let error = try_part2("rn=1,cm=x").unwrap_err();
+        assert_eq!((error.column, error.reason), (9, "Invalid focal length"));
+    }
+    #[test]
+    fn lens_array() {
+        let mut lenses = LensArray::new();
+        assert_eq!(lenses.insert("rn", 1), None);
+        assert_eq!(lenses.insert("cm", 2), None);
+        assert_eq!(lenses.insert("qp", 3), None);
+        assert_eq!(lenses.insert("rn", 4), Some(1));
+        assert_eq!(lenses.remove("cm"), Some(2));
+        assert_eq!(lenses.remove("cm"), None);
+        lenses.insert("cm", 5);
+        assert_eq!(lenses.get("rn"), Some(&4));
+        assert!(!lenses.contains_key("ot"));
+        assert_eq!(lenses.len(), 3);
+        let entries: Vec<_> = lenses.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(entries, [("rn", 4), ("qp", 3), ("cm", 5)]);
+        // rn and cm share box 0
+        let first_box: Vec<_> = lenses.boxes().next().unwrap().map(|(k, _)| *k).collect();
+        assert_eq!(first_box, ["rn", "cm"]);
+
+        let mut many = LensArray::new();
+        many.extend((0..1000).map(|i| (i.to_string(), i)));
+        (0..1000).step_by(2).for_each(|i| {
+            many.remove(&i.to_string());
+        });
+        assert_eq!(many.len(), 500);
+        assert_eq!(many.get("999"), Some(&999));
+        assert_eq!(many.iter().next(), Some((&"1".to_owned(), &1)));
+    }
+    #[test]
+    fn hashers() {
+        let mut hasher = BuildSimpleHasher.build_hasher();
+        hasher.write(b"HASH");
+        assert_eq!(hasher.finish(), 52);
+        let mut map = std::collections::HashMap::with_hasher(BuildSimpleHasher);
+        map.insert("rn", 1);
+        map.insert("qp", 3);
+        assert_eq!((map["rn"], map["qp"]), (1, 3));
+        let lenses: LensArray<&str, u8, std::collections::hash_map::RandomState> =
+            [("rn", 1), ("qp", 3), ("rn", 2)].into_iter().collect();
+        assert_eq!(
+            lenses.iter().collect::<Vec<_>>(),
+            [(&"rn", &2), (&"qp", &3)]
+        );
     }
     #[test]
     fn test_input_part2() {
@@ -152,6 +398,9 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 296921);
+        let Some(input) = puzzle_input(15) else {
+            return;
+        };
+        assert_eq!(part2(&input), 296921);
     }
 }
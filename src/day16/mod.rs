@@ -1,110 +1,106 @@
-use std::ops::{Index, IndexMut};
-
 use crate::two_iter::TwoIter;
 
+use crate::{
+    geometry::{Direction, Position},
+    grid::Grid,
+    parse::{ParseError, ParseResult},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
-pub fn part1(input: &str) -> usize {
-    LightMaze::parse(input).energized()
-}
+pub struct Day16;
 
-pub fn part2(input: &str) -> usize {
-    LightMaze::parse(input).max_energized()
-}
+impl Solution for Day16 {
+    const DAY: u8 = 16;
+    type Parsed<'a> = LightMaze;
 
-struct LightMaze {
-    instruments: Vec<Vec<Instrument>>,
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        LightMaze::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(maze: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(maze.energized().into())
+    }
+    fn part2(maze: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(maze.max_energized().into())
+    }
 }
 
-struct VisitedCache {
-    visited: Vec<Visited>,
-    width: usize,
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
 }
 
-impl VisitedCache {
-    fn new(width: usize, height: usize) -> Self {
-        Self {
-            visited: vec![Visited::None; width * height],
-            width,
-        }
-    }
-    fn len(&self) -> usize {
-        self.visited
-            .iter()
-            .filter(|v| !matches!(v, Visited::None))
-            .count()
-    }
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
-impl Index<Position> for VisitedCache {
-    type Output = Visited;
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+    Ok(Day16::parse(input)?.energized())
+}
 
-    fn index(&self, Position(x, y): Position) -> &Self::Output {
-        &self.visited[y * self.width + x]
-    }
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+    Ok(Day16::parse(input)?.max_energized())
 }
 
-impl IndexMut<Position> for VisitedCache {
-    fn index_mut(&mut self, Position(x, y): Position) -> &mut Self::Output {
-        &mut self.visited[y * self.width + x]
-    }
+pub struct LightMaze {
+    instruments: Grid<Instrument>,
 }
 
 impl LightMaze {
-    fn parse(input: &str) -> Self {
-        let instruments = input
-            .split('\n')
-            .map(|row| row.chars().map(Instrument::from_char).collect::<Vec<_>>())
-            .collect();
-        Self { instruments }
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let instruments = Grid::parse(input, Instrument::from_char, "Unknown instrument")?;
+        Ok(Self { instruments })
     }
 
-    fn width(&self) -> usize {
-        self.instruments.first().unwrap().len()
+    fn energized(&self) -> usize {
+        self.energized_from(Position::default(), Direction::East)
     }
 
-    fn energized(&self) -> usize {
-        let mut visited = VisitedCache::new(self.width(), self.instruments.len());
-        self.walk_path(Position::default(), Direction::East, &mut visited);
-        visited.len()
+    fn energized_from(&self, position: Position, direction: Direction) -> usize {
+        let (width, height) = (self.instruments.width(), self.instruments.height());
+        let mut visited = Grid::filled(width, height, Visited::None);
+        self.walk_path(position, direction, &mut visited);
+        visited
+            .iter()
+            .filter(|(_, v)| !matches!(v, Visited::None))
+            .count()
     }
 
     fn max_energized(&self) -> usize {
-        Direction::ALL
-            .iter()
-            .map(|initial_dir| {
-                (0..self.width())
-                    .map(|i| {
-                        let mut visited = VisitedCache::new(self.width(), self.instruments.len());
-                        let initial_position = match initial_dir {
-                            Direction::North => Position(i, self.instruments.len() - 1),
-                            Direction::East => Position(0, i),
-                            Direction::South => Position(i, 0),
-                            Direction::West => Position(self.width() - 1, i),
-                        };
-                        self.walk_path(initial_position, *initial_dir, &mut visited);
-                        visited.len()
-                    })
-                    .max()
-                    .unwrap()
-            })
+        let (width, height) = (self.instruments.width(), self.instruments.height());
+        let vertical = (0..width).flat_map(|x| {
+            [
+                (Position(x, height - 1), Direction::North),
+                (Position(x, 0), Direction::South),
+            ]
+        });
+        let horizontal = (0..height).flat_map(|y| {
+            [
+                (Position(0, y), Direction::East),
+                (Position(width - 1, y), Direction::West),
+            ]
+        });
+        vertical
+            .chain(horizontal)
+            .map(|(position, direction)| self.energized_from(position, direction))
             .max()
             .unwrap()
     }
 
-    fn walk_path(&self, position: Position, direction: Direction, visited: &mut VisitedCache) {
-        let visit_type = self[position].as_visited(direction);
+    fn walk_path(&self, position: Position, direction: Direction, visited: &mut Grid<Visited>) {
+        let instrument = self.instruments[position];
+        let visit_type = instrument.as_visited(direction);
         match visited[position] {
             Visited::Both => return,
             Visited::None => visited[position] = visit_type,
             v if v == visit_type => return,
             _ => visited[position] = Visited::Both,
         }
-        self[position]
+        instrument
             .out_directions(direction)
             .for_each(|next_direction| {
-                if position.can_move(next_direction, self.width(), self.instruments.len()) {
-                    let next_position = position.move_next(next_direction);
+                if let Some(next_position) = self.instruments.step(position, next_direction) {
                     self.walk_path(next_position, next_direction, visited);
                 }
             });
@@ -119,11 +115,12 @@ enum Visited {
     None,
 }
 
-impl Index<Position> for LightMaze {
-    type Output = Instrument;
-
-    fn index(&self, Position(x, y): Position) -> &Self::Output {
-        &self.instruments[y][x]
+impl Visited {
+    fn along(direction: Direction) -> Self {
+        match direction {
+            Direction::North | Direction::South => Self::Vertical,
+            Direction::East | Direction::West => Self::Horizontal,
+        }
     }
 }
 
@@ -137,14 +134,14 @@ enum Instrument {
 }
 
 impl Instrument {
-    fn from_char(c: char) -> Self {
+    fn from_char(c: char) -> Option<Self> {
         match c {
-            '.' => Self::None,
-            '|' => Self::VSplitter,
-            '-' => Self::HSplitter,
-            '\\' => Self::NWSEMirror,
-            '/' => Self::NESWMirror,
-            _ => panic!("Unknown instrument"),
+            '.' => Some(Self::None),
+            '|' => Some(Self::VSplitter),
+            '-' => Some(Self::HSplitter),
+            '\\' => Some(Self::NWSEMirror),
+            '/' => Some(Self::NESWMirror),
+            _ => None,
         }
     }
 
@@ -177,57 +174,17 @@ impl Instrument {
             (Self::NWSEMirror, _) => Visited::Horizontal,
             (Self::NESWMirror, Direction::East | Direction::South) => Visited::Horizontal,
             (Self::NESWMirror, _) => Visited::Vertical,
-            (_, direction) => direction.as_visited(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-enum Direction {
-    North,
-    East,
-    South,
-    West,
-}
-
-impl Direction {
-    const ALL: [Direction; 4] = [Self::North, Self::East, Self::South, Self::West];
-    fn as_visited(&self) -> Visited {
-        match self {
-            Direction::North | Direction::South => Visited::Vertical,
-            Direction::East | Direction::West => Visited::Horizontal,
+            (_, direction) => Visited::along(direction),
         }
     }
 }
 
 type OutDirections = TwoIter<Direction>;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
-struct Position(usize, usize);
-
-impl Position {
-    fn move_next(&self, direction: Direction) -> Self {
-        match direction {
-            Direction::North => Position(self.0, self.1 - 1),
-            Direction::South => Position(self.0, self.1 + 1),
-            Direction::East => Position(self.0 + 1, self.1),
-            Direction::West => Position(self.0 - 1, self.1),
-        }
-    }
-
-    fn can_move(&self, direction: Direction, max_len: usize, max_height: usize) -> bool {
-        match direction {
-            Direction::North => self.1 > 0,
-            Direction::South => self.1 < max_height - 1,
-            Direction::East => self.0 < max_len - 1,
-            Direction::West => self.0 > 0,
-        }
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#".|...\....
 |.-.\.....
@@ -246,7 +203,15 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 6361);
+        let Some(input) = puzzle_input(16) else {
+            return;
+        };
+        assert_eq!(part1(&input), 6361);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part1(".|.\n.+.").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 2, "+"));
     }
     #[test]
     fn test_input_part2() {
@@ -254,6 +219,9 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 6701);
+        let Some(input) = puzzle_input(16) else {
+            return;
+        };
+        assert_eq!(part2(&input), 6701);
     }
 }
@@ -1,314 +1,240 @@
-use std::ops::{Index, IndexMut};
+use std::{cmp::Reverse, collections::BinaryHeap, fmt, ops::Index};
 
-pub const PUZZLE_INPUT: &str = include_str!("input.txt");
+use crate::{
+    geometry::{Direction, Position},
+    grid::Grid,
+    parse::{ParseError, ParseResult},
+    solution::{Answer, Solution, SolveError},
+};
 
-pub fn part1(input: &str) -> usize {
-    City::parse(input).shortest_path()
-}
+#[cfg(feature = "bundled-inputs")]
+pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
-pub fn part2(input: &str) -> usize {
-    todo!();
-}
+pub struct Day17;
 
-struct City {
-    height: usize,
-    width: usize,
-    blocks: Vec<u32>,
-}
+impl Solution for Day17 {
+    const DAY: u8 = 17;
+    type Parsed<'a> = City;
 
-impl City {
-    fn parse(input: &str) -> Self {
-        let width = input.find('\n').unwrap();
-        let blocks: Vec<_> = input
-            .split('\n')
-            .flat_map(|row| row.chars().map(|c| c.to_digit(10).unwrap()))
-            .collect();
-        let height = blocks.len() / width;
-
-        Self {
-            width,
-            blocks,
-            height,
-        }
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        City::parse(input).map_err(|error| error.locate(Self::DAY, input))
     }
-
-    fn shortest_path(&self) -> usize {
-        let mut nodes = Nodes::new(self.width, self.height);
-
-        for _ in 0..(self.width * self.height * 9) {
-            let Some(min_node) = nodes.min() else {
-                break;
-            };
-            if min_node.position == Position(self.width - 1, self.height - 1) {
-                break;
-            }
-            let min_node = min_node.clone();
-            for direction in Direction::ALL
-                .into_iter()
-                .filter(|direction| min_node.can_move(*direction, self.width, self.height))
-            {
-                let position = min_node.position.move_next(direction);
-                for (heat_loss, incoming_dir, mut steps_in_same_direction) in
-                    min_node.heat_loss_iter()
-                {
-                    let new_node = &mut nodes[position];
-                    let new_steps_in_same_direction = if incoming_dir == direction {
-                        match steps_in_same_direction.next() {
-                            None => {
-                                dbg!(&position);
-                                dbg!(&direction);
-                                dbg!(&incoming_dir);
-                                continue;
-                            }
-                            Some(v) => v,
-                        }
-                    } else {
-                        Default::default()
-                    };
-                    if new_node.heat_loss(direction, new_steps_in_same_direction)
-                        > &mut heat_loss.saturating_add(self[position])
-                    {
-                        *new_node.heat_loss(direction, new_steps_in_same_direction) =
-                            heat_loss + self[position];
-                        nodes.mark_unvisited(position, new_steps_in_same_direction, direction);
-                    }
-                    nodes.mark_visited(min_node.position, steps_in_same_direction, direction);
-                }
-            }
-        }
-        //dbg!(nodes.nodes.iter().collect::<Vec<_>>());
-        dbg!(&nodes[Position(self.width - 1, self.height - 1)])
-            .min_heat_loss()
-            .0 as usize
+    fn part1(city: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        city.min_heat_loss(Crucible::NORMAL)
+            .map(Answer::from)
+            .map_err(SolveError::new)
+    }
+    fn part2(city: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        city.min_heat_loss(Crucible::ULTRA)
+            .map(Answer::from)
+            .map_err(SolveError::new)
     }
 }
 
-struct Nodes {
-    nodes: Vec<Node>,
-    width: usize,
-    visited: Vec<[bool; 12]>,
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
 }
 
-impl Nodes {
-    fn new(width: usize, height: usize) -> Self {
-        let mut nodes = Vec::with_capacity(height * width);
-        for y in 0..height {
-            for x in 0..width {
-                nodes.push(Node::new(Position(x, y)));
-            }
-        }
-        let visited = vec![[false; 12]; height * width];
-
-        nodes[0] = Node::initial();
-        Self {
-            nodes,
-            width,
-            visited,
-        }
-    }
-
-    fn min(&self) -> Option<&Node> {
-        self.nodes
-            .iter()
-            .map(|node| {
-                let min = node
-                    .heat_loss_iter()
-                    .filter(|(_, dir, steps_in_same_direction)| {
-                        self.is_visited(node.position, *steps_in_same_direction, *dir)
-                    })
-                    .map(|(heat_loss, _, _)| heat_loss)
-                    .min();
-                (min, node)
-            })
-            .min()
-            .map(|(_, node)| node)
-    }
-
-    fn mark_visited(
-        &mut self,
-        Position(x, y): Position,
-        steps_in_same_direction: StepsInSameDirection,
-        direction: Direction,
-    ) {
-        self.visited[y * self.width + x]
-            [direction as usize * 3 + steps_in_same_direction as usize] = true;
-    }
-    fn mark_unvisited(
-        &mut self,
-        Position(x, y): Position,
-        steps_in_same_direction: StepsInSameDirection,
-        direction: Direction,
-    ) {
-        for direction in Direction::ALL.iter() {
-            //.filter(|dir| *dir != &direction) {
-            for step in StepsInSameDirection::ALL
-                .iter()
-                .filter(|step| *step <= &steps_in_same_direction)
-            {
-                self.visited[y * self.width + x][*direction as usize * 3 + *step as usize] = false;
-            }
-        }
-    }
-    fn is_visited(
-        &self,
-        Position(x, y): Position,
-        steps_in_same_direction: StepsInSameDirection,
-        direction: Direction,
-    ) -> bool {
-        self.visited[y * self.width + x][direction as usize * 3 + steps_in_same_direction as usize]
-    }
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
-#[derive(Default, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Debug)]
-enum StepsInSameDirection {
-    #[default]
-    One,
-    Two,
-    Three,
+pub fn try_part1(input: &str) -> Result<usize, RouteError> {
+    Day17::parse(input)?.min_heat_loss(Crucible::NORMAL)
 }
 
-impl StepsInSameDirection {
-    const ALL: [Self; 3] = [Self::One, Self::Two, Self::Three];
+pub fn try_part2(input: &str) -> Result<usize, RouteError> {
+    Day17::parse(input)?.min_heat_loss(Crucible::ULTRA)
 }
 
-impl Iterator for StepsInSameDirection {
-    type Item = Self;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+    Parse(ParseError),
+    NoRoute,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            StepsInSameDirection::One => Some(StepsInSameDirection::Two),
-            StepsInSameDirection::Two => Some(StepsInSameDirection::Three),
-            StepsInSameDirection::Three => None,
-        }
+impl From<ParseError> for RouteError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
     }
 }
 
-impl From<usize> for StepsInSameDirection {
-    fn from(value: usize) -> Self {
-        match value {
-            0 => StepsInSameDirection::One,
-            1 => StepsInSameDirection::Two,
-            2 => StepsInSameDirection::Three,
-            _ => panic!("Cannot convert number greater than 3"),
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::Parse(error) => error.fmt(f),
+            RouteError::NoRoute => write!(f, "No route through the city"),
         }
     }
 }
 
-impl Index<Position> for Nodes {
-    type Output = Node;
+impl std::error::Error for RouteError {}
 
-    fn index(&self, Position(x, y): Position) -> &Self::Output {
-        &self.nodes[y * self.width + x]
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crucible {
+    pub min_run: usize,
+    pub max_run: usize,
 }
-impl IndexMut<Position> for Nodes {
-    fn index_mut(&mut self, Position(x, y): Position) -> &mut Self::Output {
-        &mut self.nodes[y * self.width + x]
-    }
+
+impl Crucible {
+    pub const NORMAL: Self = Self {
+        min_run: 1,
+        max_run: 3,
+    };
+    pub const ULTRA: Self = Self {
+        min_run: 4,
+        max_run: 10,
+    };
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
-struct Node {
-    position: Position,
-    heat_loss: [[u32; 3]; 4],
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub heat_loss: usize,
+    pub positions: Vec<Position>,
 }
 
-impl PartialOrd for Node {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
+pub struct City {
+    blocks: Grid<u32>,
 }
-impl Ord for Node {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.min_heat_loss().0.cmp(&other.min_heat_loss().0)
+
+impl City {
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let blocks = Grid::parse(input, |c| c.to_digit(10), "Expected a digit")?;
+        Ok(Self { blocks })
     }
-}
 
-impl Node {
-    fn new(position: Position) -> Self {
-        Self {
-            position,
-            heat_loss: [[u32::MAX; 3]; 4],
-        }
+    fn min_heat_loss(&self, crucible: Crucible) -> Result<usize, RouteError> {
+        self.shortest_path(crucible)
+            .map(|path| path.heat_loss)
+            .ok_or(RouteError::NoRoute)
     }
-    fn initial() -> Self {
-        Self {
-            position: Position::default(),
-            heat_loss: [[0, u32::MAX, u32::MAX]; 4],
+
+    pub fn shortest_path(&self, crucible: Crucible) -> Option<Path> {
+        let (width, height) = (self.blocks.width(), self.blocks.height());
+        let end = Position(width - 1, height - 1);
+        if end == Position::default() {
+            return Some(Path {
+                heat_loss: 0,
+                positions: vec![end],
+            });
+        }
+        let states = State::count(width, height, crucible);
+        let mut heat_losses = vec![usize::MAX; states];
+        let mut previous = vec![None; states];
+        let mut queue = BinaryHeap::new();
+
+        for direction in [Direction::East, Direction::South] {
+            let start = State {
+                position: Position::default(),
+                direction,
+                run: 0,
+            };
+            heat_losses[start.index(width, crucible)] = 0;
+            queue.push(Reverse((0, start)));
         }
-    }
 
-    fn can_move(&self, direction: Direction, max_len: usize, max_height: usize) -> bool {
-        self.position.can_move(direction, max_len, max_height)
+        while let Some(Reverse((heat_loss, state))) = queue.pop() {
+            let index = state.index(width, crucible);
+            if heat_loss > heat_losses[index] {
+                continue;
+            }
+            if state.position == end && state.run >= crucible.min_run {
+                return Some(self.path(state, heat_loss, &previous, crucible));
+            }
+            for next in state.next(crucible) {
+                let Some(next) = next.moved(&self.blocks) else {
+                    continue;
+                };
+                let next_heat_loss = heat_loss + self[next.position] as usize;
+                let next_index = next.index(width, crucible);
+                if next_heat_loss < heat_losses[next_index] {
+                    heat_losses[next_index] = next_heat_loss;
+                    previous[next_index] = Some(state);
+                    queue.push(Reverse((next_heat_loss, next)));
+                }
+            }
+        }
+        None
     }
 
-    fn min_heat_loss(&self) -> (u32, Direction, StepsInSameDirection) {
-        self.heat_loss_iter().min().unwrap()
-    }
-    fn heat_loss_iter(&self) -> impl Iterator<Item = (u32, Direction, StepsInSameDirection)> + '_ {
-        self.heat_loss.iter().enumerate().flat_map(|(index, row)| {
-            row.iter()
-                .enumerate()
-                .map(move |(steps_in_same_direction, heat_loss)| {
-                    (
-                        *heat_loss,
-                        Direction::ALL[index],
-                        steps_in_same_direction.into(),
-                    )
-                })
-        })
-    }
-    fn heat_loss(&mut self, direction: Direction, visit_number: StepsInSameDirection) -> &mut u32 {
-        &mut self.heat_loss[direction as usize][visit_number as usize]
+    fn path(
+        &self,
+        end: State,
+        heat_loss: usize,
+        previous: &[Option<State>],
+        crucible: Crucible,
+    ) -> Path {
+        let mut positions = vec![end.position];
+        let mut state = end;
+        while let Some(before) = previous[state.index(self.blocks.width(), crucible)] {
+            positions.push(before.position);
+            state = before;
+        }
+        positions.reverse();
+        Path {
+            heat_loss,
+            positions,
+        }
     }
 }
 
 impl Index<Position> for City {
     type Output = u32;
 
-    fn index(&self, Position(x, y): Position) -> &Self::Output {
-        &self.blocks[y * self.width + x]
+    fn index(&self, position: Position) -> &Self::Output {
+        &self.blocks[position]
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum Direction {
-    North,
-    East,
-    South,
-    West,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct State {
+    position: Position,
+    direction: Direction,
+    run: usize,
 }
 
-impl Direction {
-    const ALL: [Direction; 4] = [Self::North, Self::East, Self::South, Self::West];
-}
+impl State {
+    fn count(width: usize, height: usize, crucible: Crucible) -> usize {
+        width * height * Direction::ALL.len() * (crucible.max_run + 1)
+    }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
-struct Position(usize, usize);
+    fn index(&self, width: usize, crucible: Crucible) -> usize {
+        let Position(x, y) = self.position;
+        ((y * width + x) * Direction::ALL.len() + self.direction as usize) * (crucible.max_run + 1)
+            + self.run
+    }
 
-impl Position {
-    fn move_next(&self, direction: Direction) -> Self {
-        match direction {
-            Direction::North => Position(self.0, self.1 - 1),
-            Direction::South => Position(self.0, self.1 + 1),
-            Direction::East => Position(self.0 + 1, self.1),
-            Direction::West => Position(self.0 - 1, self.1),
-        }
+    // The states reachable in one step, before moving within the city
+    fn next(self, crucible: Crucible) -> impl Iterator<Item = Self> {
+        let straight = (self.run < crucible.max_run).then_some(Self {
+            run: self.run + 1,
+            ..self
+        });
+        let turns = (self.run >= crucible.min_run)
+            .then(|| {
+                [self.direction.left(), self.direction.right()].map(|direction| Self {
+                    direction,
+                    run: 1,
+                    ..self
+                })
+            })
+            .into_iter()
+            .flatten();
+        straight.into_iter().chain(turns)
     }
 
-    fn can_move(&self, direction: Direction, max_len: usize, max_height: usize) -> bool {
-        match direction {
-            Direction::North => self.1 > 0,
-            Direction::South => self.1 < max_height - 1,
-            Direction::East => self.0 < max_len - 1,
-            Direction::West => self.0 > 0,
-        }
+    fn moved<T>(self, grid: &Grid<T>) -> Option<Self> {
+        Some(Self {
+            position: grid.step(self.position, self.direction)?,
+            ..self
+        })
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"2413432311323
 3215453535623
@@ -330,16 +256,50 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part1() {
-        // assert_eq!(part1(PUZZLE_INPUT), 0);
-        // less than 883
-        // greater than 864
+        let Some(input) = puzzle_input(17) else {
+            return;
+        };
+        assert_eq!(part1(&input), 866);
+    }
+    #[test]
+    fn path() {
+        let city = Day17::parse(TEST_INPUT).unwrap();
+        let path = city.shortest_path(Crucible::NORMAL).unwrap();
+        assert_eq!(path.positions.first(), Some(&Position(0, 0)));
+        assert_eq!(path.positions.last(), Some(&Position(12, 12)));
+        assert!(path
+            .positions
+            .windows(2)
+            .all(|pair| pair[0].0.abs_diff(pair[1].0) + pair[0].1.abs_diff(pair[1].1) == 1));
+        let heat_loss: u32 = path.positions[1..].iter().map(|p| city[*p]).sum();
+        assert_eq!(heat_loss as usize, path.heat_loss);
+    }
+    #[test]
+    fn malformed_input() {
+        let Err(RouteError::Parse(error)) = try_part1("241\n3x5") else {
+            panic!("Expected a parse error");
+        };
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 2, "x"));
+    }
+    #[test]
+    fn no_route() {
+        assert_eq!(try_part2("12\n34"), Err(RouteError::NoRoute));
+        assert_eq!(try_part1("7"), Ok(0));
+        assert_eq!(try_part2("7"), Ok(0));
     }
     #[test]
     fn test_input_part2() {
-        assert_eq!(part2(TEST_INPUT), 0);
+        assert_eq!(part2(TEST_INPUT), 94);
+        assert_eq!(
+            part2("111111111111\n999999999991\n999999999991\n999999999991\n999999999991"),
+            71
+        );
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 0);
+        let Some(input) = puzzle_input(17) else {
+            return;
+        };
+        assert_eq!(part2(&input), 1010);
     }
 }
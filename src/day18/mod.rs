@@ -1,126 +1,164 @@
+use std::fmt;
+
+use crate::{
+    geometry::Direction,
+    parse::{ParseError, ParseResult, ParseStr},
+    polygon::{Overflow, Polygon},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day18;
+
+impl Solution for Day18 {
+    const DAY: u8 = 18;
+    type Parsed<'a> = (Instructions, Instructions);
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Instructions::parse(input)
+            .and_then(|part1| Ok((part1, Instructions::parse_part2(input)?)))
+            .map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1((part1, _): &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        part1.area().map(Answer::from).map_err(SolveError::new)
+    }
+    fn part2((_, part2): &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        part2.area().map(Answer::from).map_err(SolveError::new)
+    }
+}
+
 pub fn part1(input: &str) -> usize {
-    Instructions::parse(input).area()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    Instructions::parse_part2(input).area()
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<usize, DigError> {
+    Ok(Instructions::parse(input)
+        .map_err(|error| error.locate(Day18::DAY, input))?
+        .area()?)
+}
+
+pub fn try_part2(input: &str) -> Result<usize, DigError> {
+    Ok(Instructions::parse_part2(input)
+        .map_err(|error| error.locate(Day18::DAY, input))?
+        .area()?)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DigError {
+    Parse(ParseError),
+    Overflow(Overflow),
+}
+
+impl From<ParseError> for DigError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl From<Overflow> for DigError {
+    fn from(error: Overflow) -> Self {
+        Self::Overflow(error)
+    }
+}
+
+impl fmt::Display for DigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DigError::Parse(error) => error.fmt(f),
+            DigError::Overflow(error) => error.fmt(f),
+        }
+    }
 }
 
-struct Instructions {
+impl std::error::Error for DigError {}
+
+pub struct Instructions {
     instructions: Vec<Instruction>,
 }
 
 impl Instructions {
-    fn parse(input: &str) -> Self {
-        let instructions = input.split('\n').map(Instruction::parse).collect();
-        Self { instructions }
-    }
-    fn parse_part2(input: &str) -> Self {
-        let instructions = input.split('\n').map(Instruction::parse_part2).collect();
-        Self { instructions }
-    }
-    fn area(&self) -> usize {
-        let mut area = 0;
-        let mut perimeter = 0;
-
-        let mut x = 0;
-        let mut y = 0;
-        for Instruction {
-            direction, amount, ..
-        } in self.instructions.iter()
-        {
-            // Shoelace formula
-            match direction {
-                Direction::Left => {
-                    area += amount * y;
-                    x -= amount;
-                }
-                Direction::Right => {
-                    area -= amount * y;
-                    x += amount;
-                }
-                Direction::Up => {
-                    area += amount * x;
-                    y += amount;
-                }
-                Direction::Down => {
-                    area -= amount * x;
-                    y -= amount;
-                }
-            }
-            perimeter += amount;
-        }
-        // Since the shoelace formula will be missing the thick 'boarder',
-        // we add half the perimeter plus one for the exterior corners
-        ((area.unsigned_abs() + perimeter.unsigned_abs()) / 2 + 1) as usize
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let instructions = input
+            .split('\n')
+            .map(Instruction::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { instructions })
+    }
+    fn parse_part2(input: &str) -> ParseResult<'_, Self> {
+        let instructions = input
+            .split('\n')
+            .map(Instruction::parse_part2)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { instructions })
+    }
+    fn area(&self) -> Result<usize, Overflow> {
+        Polygon::from_instructions(
+            self.instructions
+                .iter()
+                .map(|instruction| (instruction.direction, instruction.amount)),
+        )?
+        .thick_area()
     }
 }
 
 struct Instruction {
-    amount: i64,
+    amount: isize,
     direction: Direction,
 }
 
 impl Instruction {
-    fn parse(input: &str) -> Self {
-        let (dir, rest) = input.split_once(' ').unwrap();
-        let (amount, _) = rest.split_once(' ').unwrap();
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let (dir, rest) = input.split_once_or(" ", "Expected a direction")?;
+        let (amount, _) = rest.split_once_or(" ", "Expected an amount and colour")?;
 
-        Self {
-            direction: Direction::parse(dir),
-            amount: amount.parse().unwrap(),
-        }
+        Ok(Self {
+            direction: parse_direction(dir)?,
+            amount: amount.parse_or("Couldn't parse amount")?,
+        })
     }
 
-    fn parse_part2(input: &str) -> Self {
-        let instruction = input
-            .split(' ')
-            .last()
-            .unwrap()
-            .strip_prefix("(#")
-            .unwrap()
-            .strip_suffix(')')
-            .unwrap();
-
-        Self {
-            direction: Direction::parse(&instruction[5..]),
-            amount: i64::from_str_radix(&instruction[0..5], 16).unwrap(),
+    fn parse_part2(input: &str) -> ParseResult<'_, Self> {
+        let (_, colour) = input.rsplit_once(' ').unwrap_or(("", input));
+        let instruction = colour
+            .strip_prefix_or("(#", "Expected a colour")?
+            .strip_suffix_or(")", "Expected a colour")?;
+        if instruction.len() != 6 || !instruction.is_char_boundary(5) {
+            return Err(instruction.invalid("Expected six hex digits"));
         }
-    }
-}
 
-#[derive(Clone, Copy, Debug)]
-enum Direction {
-    Left,
-    Right,
-    Up,
-    Down,
+        Ok(Self {
+            direction: parse_direction(&instruction[5..])?,
+            amount: isize::from_str_radix(&instruction[0..5], 16)
+                .map_err(|_| instruction[0..5].invalid("Couldn't parse amount"))?,
+        })
+    }
 }
 
-impl Direction {
-    fn parse(input: &str) -> Self {
-        input.chars().map(Self::from_char).next().unwrap()
-    }
-    fn from_char(c: char) -> Self {
-        match c {
-            'R' => Self::Right,
-            'L' => Self::Left,
-            'U' => Self::Up,
-            'D' => Self::Down,
-            '0' => Self::Right,
-            '1' => Self::Down,
-            '2' => Self::Left,
-            '3' => Self::Up,
-            _ => panic!("Unrecognized direction"),
-        }
-    }
+fn parse_direction(input: &str) -> ParseResult<'_, Direction> {
+    let mut chars = input.chars();
+    let direction = match (chars.next(), chars.next()) {
+        (Some('R' | '0'), None) => Direction::East,
+        (Some('L' | '2'), None) => Direction::West,
+        (Some('U' | '3'), None) => Direction::North,
+        (Some('D' | '1'), None) => Direction::South,
+        _ => return Err(input.invalid("Unrecognized direction")),
+    };
+    Ok(direction)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{
+        input::puzzle_input,
+        solution::{Part, Puzzle},
+    };
 
     const TEST_INPUT: &str = r#"R 6 (#70c710)
 D 5 (#0dc571)
@@ -143,7 +181,36 @@ U 2 (#7a21e3)"#;
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 56923);
+        let Some(input) = puzzle_input(18) else {
+            return;
+        };
+        assert_eq!(part1(&input), 56923);
+    }
+    #[test]
+    fn malformed_input() {
+        let Err(DigError::Parse(error)) = try_part1("R 6 (#70c710)\nX 5 (#0dc571)") else {
+            panic!("Expected a parse error");
+        };
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 1, "X"));
+        let Err(DigError::Parse(error)) = try_part2("R 6 (#70c710)\nD 5 (#0dc574)") else {
+            panic!("Expected a parse error");
+        };
+        assert_eq!(
+            (error.line, error.column, error.text.as_str()),
+            (2, 12, "4")
+        );
+    }
+    #[test]
+    fn overflow() {
+        let input = "R 9223372036854775807 (#000000)\nD 1 (#000000)\nL 1 (#000000)";
+        assert_eq!(try_part1(input), Err(DigError::Overflow(Overflow)));
+        let input = "R 4294967296 (#000000)\nD 4294967296 (#000000)\nL 4294967296 (#000000)";
+        assert_eq!(try_part1(input), Err(DigError::Overflow(Overflow)));
+        let error = Day18.solve(input, Part::One).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Day 18 part 1: Polygon is too large to measure"
+        );
     }
     #[test]
     fn test_input_part2() {
@@ -151,6 +218,9 @@ U 2 (#7a21e3)"#;
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 66296566363189);
+        let Some(input) = puzzle_input(18) else {
+            return;
+        };
+        assert_eq!(part2(&input), 66296566363189);
     }
 }
@@ -1,20 +1,53 @@
 use std::{
     cmp::Ordering::{Equal, Greater, Less},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fmt,
     ops::Range,
 };
 
+use crate::{
+    graph::{self, Dot},
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day19;
+
+impl Solution for Day19 {
+    const DAY: u8 = 19;
+    type Parsed<'a> = Sorter<'a>;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Sorter::parse_sorting(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(sorter: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(sorter.accepted_part_total().into())
+    }
+    fn part2(sorter: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(sorter.process_all().into())
+    }
+}
+
 pub fn part1(input: &str) -> usize {
-    Sorter::parse(input).accepted_part_total()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    Sorter::parse(input).process_all()
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+    Ok(Day19::parse(input)?.accepted_part_total())
+}
+
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+    Ok(Day19::parse(input)?.process_all())
 }
 
-struct Sorter<'a> {
+pub struct Sorter<'a> {
     workflows: HashMap<&'a str, Workflow<'a>>,
     parts: Vec<MachinePart>,
 }
@@ -24,23 +57,180 @@ impl<'a> Sorter<'a> {
     const REJECTED: &'static str = "R";
     const START: &'static str = "in";
 
-    fn parse(input: &'a str) -> Self {
-        let (workflows, parts) = input.split_once("\n\n").unwrap();
+    fn parse(input: &'a str) -> ParseResult<'a, Self> {
+        let (workflows, parts) =
+            input.split_once_or("\n\n", "No blank line between workflows and parts")?;
 
-        Self {
+        Ok(Self {
             workflows: workflows
                 .split('\n')
-                .map(Workflow::parse)
-                .map(|workflow| (workflow.name, workflow))
-                .collect(),
-            parts: parts.split('\n').map(MachinePart::parse).collect(),
+                .map(|workflow| Workflow::parse(workflow).map(|workflow| (workflow.name, workflow)))
+                .collect::<Result<_, _>>()?,
+            parts: parts
+                .split('\n')
+                .map(MachinePart::parse)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    // Accepts workflows that can't sort every part, to analyse or draw them
+    pub fn parse_unchecked(input: &'a str) -> Result<Self, ParseError> {
+        Self::parse(input).map_err(|error| error.locate(Day19::DAY, input))
+    }
+
+    // Only accepts workflows that take every part from `in` to A or R, so parts can be processed
+    fn parse_sorting(input: &'a str) -> ParseResult<'a, Self> {
+        let sorter = Self::parse(input)?;
+        if let Some(rule) = sorter
+            .workflow_names()
+            .into_iter()
+            .filter_map(|name| sorter.workflows[name].rules.last())
+            .find(|rule| !matches!(rule.comp, Comparison::MatchAll))
+        {
+            return Err(rule.destination.invalid("Last rule has a condition"));
+        }
+        if !sorter.workflows.contains_key(Self::START) {
+            return Err(input[..0].invalid("No in workflow"));
         }
+        let analysis = sorter.analyse();
+        if let Some((_, destination)) = analysis.undefined.first() {
+            return Err(destination.invalid("Unknown workflow"));
+        }
+        if let Some(cycle) = analysis.cycles.first() {
+            return Err(cycle[0].invalid("Workflows form a cycle"));
+        }
+        Ok(sorter)
     }
 
     fn accepted_part_total(&self) -> usize {
         self.parts.iter().map(|part| self.process_part(part)).sum()
     }
 
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new("workflows");
+        dot.node(
+            Self::ACCEPTED,
+            &[("shape", "doublecircle"), ("color", "green")],
+        )
+        .node(Self::REJECTED, &[("shape", "circle"), ("color", "red")]);
+        let mut undefined: Vec<_> = self
+            .analyse()
+            .undefined
+            .into_iter()
+            .map(|(_, destination)| destination)
+            .collect();
+        undefined.sort_unstable();
+        undefined.dedup();
+        for destination in undefined {
+            dot.node(destination, &[("style", "dashed"), ("color", "orange")]);
+        }
+        for workflow in self.workflow_names() {
+            dot.node(workflow, &[("shape", "box")]);
+            for rule in &self.workflows[workflow].rules {
+                let label = rule.comp.to_string();
+                let mut attributes = vec![];
+                if !label.is_empty() {
+                    attributes.push(("label", label.as_str()));
+                }
+                if !self.is_defined(rule.destination) {
+                    attributes.push(("style", "dashed"));
+                }
+                dot.edge(workflow, rule.destination, &attributes);
+            }
+        }
+        dot.to_string()
+    }
+
+    pub fn analyse(&self) -> Analysis<'a> {
+        let mut undefined: Vec<_> = self
+            .workflows
+            .values()
+            .flat_map(|workflow| workflow.destinations().map(|next| (workflow.name, next)))
+            .filter(|(_, next)| !self.is_defined(next))
+            .collect();
+        undefined.sort_unstable();
+        undefined.dedup();
+
+        let mut reachable = HashSet::new();
+        let mut queue: Vec<_> = self.workflows.get(Self::START).into_iter().collect();
+        while let Some(workflow) = queue.pop() {
+            if reachable.insert(workflow.name) {
+                queue.extend(
+                    workflow
+                        .destinations()
+                        .filter_map(|next| self.workflows.get(next)),
+                );
+            }
+        }
+        let unreachable = self
+            .workflow_names()
+            .into_iter()
+            .filter(|name| !reachable.contains(name))
+            .collect();
+
+        let mut cycles: Vec<_> =
+            graph::strongly_connected_components(self.workflow_names(), |name| {
+                self.workflows[name]
+                    .destinations()
+                    .filter(|next| self.workflows.contains_key(next))
+            })
+            .into_iter()
+            .filter(|component| match component[..] {
+                [name] => self.workflows[name].destinations().any(|next| next == name),
+                _ => true,
+            })
+            .collect();
+        cycles.iter_mut().for_each(|cycle| cycle.sort_unstable());
+        cycles.sort_unstable();
+
+        Analysis {
+            unreachable,
+            cycles,
+            undefined,
+        }
+    }
+
+    // Parts following an undefined workflow or returning to a workflow on their path are
+    // never accepted, so they are dropped rather than followed
+    pub fn accepted_paths(&self) -> Vec<AcceptedPath<'a>> {
+        let mut queue = vec![(vec![Self::START], MachinePartRange::default())];
+        let mut accepted = Vec::new();
+        while let Some((workflows, part)) = queue.pop() {
+            let Some(workflow) = workflows.last().and_then(|name| self.workflows.get(name)) else {
+                continue;
+            };
+            for mapped in workflow.map_all(part) {
+                if mapped.part.rating() == 0 {
+                    continue;
+                }
+                match mapped.destination {
+                    Self::ACCEPTED => accepted.push(AcceptedPath {
+                        workflows: workflows.clone(),
+                        part: mapped.part,
+                    }),
+                    Self::REJECTED => {}
+                    next if workflows.contains(&next) => {}
+                    next => {
+                        let mut workflows = workflows.clone();
+                        workflows.push(next);
+                        queue.push((workflows, mapped.part));
+                    }
+                }
+            }
+        }
+        accepted
+    }
+
+    fn workflow_names(&self) -> Vec<&'a str> {
+        let mut names: Vec<_> = self.workflows.keys().copied().collect();
+        names.sort_unstable();
+        names
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        matches!(name, Self::ACCEPTED | Self::REJECTED) || self.workflows.contains_key(name)
+    }
+
     fn process_part(&self, part: &MachinePart) -> usize {
         let mut workflow = &self.workflows[Self::START];
         loop {
@@ -53,52 +243,54 @@ impl<'a> Sorter<'a> {
     }
 
     fn process_all(&self) -> usize {
-        let mut parts = vec![MappedRange {
-            destination: "in",
-            part: MachinePartRange::default(),
-        }];
-        let mut accepted = Vec::new();
-        loop {
-            match parts.pop() {
-                None => break,
-                Some(part) => self.workflows[part.destination]
-                    .map_all(part.part)
-                    .into_iter()
-                    .for_each(|part| match part.destination {
-                        Self::ACCEPTED => accepted.push(part.part),
-                        Self::REJECTED => {}
-                        _ => parts.push(part),
-                    }),
-            }
-        }
-        accepted.iter().map(MachinePartRange::rating).sum()
+        self.accepted_paths()
+            .iter()
+            .map(|path| path.part.rating())
+            .sum()
     }
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Analysis<'a> {
+    pub unreachable: Vec<&'a str>,
+    pub cycles: Vec<Vec<&'a str>>,
+    // Pairs of the workflow and the destination it names
+    pub undefined: Vec<(&'a str, &'a str)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptedPath<'a> {
+    pub workflows: Vec<&'a str>,
+    pub part: MachinePartRange,
+}
+
 struct Workflow<'a> {
     name: &'a str,
     rules: Vec<Rule<'a>>,
 }
 
 impl<'a> Workflow<'a> {
-    fn parse(input: &'a str) -> Self {
-        let (name, rest) = input.split_once('{').unwrap();
+    fn parse(input: &'a str) -> ParseResult<'a, Self> {
+        let (name, rest) = input.split_once_or("{", "Expected opening brace")?;
 
-        Self {
+        Ok(Self {
             name,
             rules: rest
-                .strip_suffix('}')
-                .unwrap()
+                .strip_suffix_or("}", "Expected closing brace")?
                 .split(',')
                 .map(Rule::parse)
-                .collect(),
-        }
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn destinations(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.rules.iter().map(|rule| rule.destination)
     }
 
     fn map(&self, part: &MachinePart) -> &'a str {
         self.rules.iter().find_map(|rule| rule.map(part)).unwrap()
     }
-    fn map_all(&self, part: MachinePartRange) -> Vec<MappedRange> {
+    fn map_all(&self, part: MachinePartRange) -> Vec<MappedRange<'a>> {
         let mut mapped_ranges = Vec::new();
         let mut current_part = part;
         for rule in &self.rules {
@@ -122,16 +314,16 @@ struct Rule<'a> {
 }
 
 impl<'a> Rule<'a> {
-    fn parse(input: &'a str) -> Self {
+    fn parse(input: &'a str) -> ParseResult<'a, Self> {
         match input.split_once(':') {
-            None => Self {
+            None => Ok(Self {
                 destination: input,
                 comp: Comparison::MatchAll,
-            },
-            Some((comparison, destination)) => Self {
+            }),
+            Some((comparison, destination)) => Ok(Self {
                 destination,
-                comp: Comparison::parse(comparison),
-            },
+                comp: Comparison::parse(comparison)?,
+            }),
         }
     }
 
@@ -215,19 +407,35 @@ enum Comparison {
 }
 
 impl Comparison {
-    fn parse(input: &str) -> Self {
+    fn parse(input: &str) -> ParseResult<'_, Self> {
         if let Some((field, value)) = input.split_once('>') {
-            Self::GreaterThan(Field::parse(field), value.parse().unwrap())
+            Ok(Self::GreaterThan(
+                Field::parse(field)?,
+                value.parse_or("Couldn't parse value")?,
+            ))
         } else if let Some((field, value)) = input.split_once('<') {
-            Self::LessThan(Field::parse(field), value.parse().unwrap())
+            Ok(Self::LessThan(
+                Field::parse(field)?,
+                value.parse_or("Couldn't parse value")?,
+            ))
         } else {
-            Self::MatchAll
+            Err(input.invalid("Expected a comparison"))
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Comparison::LessThan(field, value) => write!(f, "{field}<{value}"),
+            Comparison::GreaterThan(field, value) => write!(f, "{field}>{value}"),
+            Comparison::MatchAll => Ok(()),
         }
     }
 }
 
-#[derive(Clone, Copy)]
-enum Field {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
     X,
     M,
     A,
@@ -235,17 +443,32 @@ enum Field {
 }
 
 impl Field {
-    fn parse(input: &str) -> Self {
-        input.chars().map(Self::from_char).next().unwrap()
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let mut chars = input.chars();
+        match (chars.next().and_then(Self::from_char), chars.next()) {
+            (Some(field), None) => Ok(field),
+            _ => Err(input.invalid("Unknown field")),
+        }
     }
 
-    fn from_char(c: char) -> Self {
+    fn from_char(c: char) -> Option<Self> {
         match c {
-            'x' => Self::X,
-            'm' => Self::M,
-            'a' => Self::A,
-            's' => Self::S,
-            _ => panic!("Unknown field"),
+            'x' => Some(Self::X),
+            'm' => Some(Self::M),
+            'a' => Some(Self::A),
+            's' => Some(Self::S),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::X => write!(f, "x"),
+            Field::M => write!(f, "m"),
+            Field::A => write!(f, "a"),
+            Field::S => write!(f, "s"),
         }
     }
 }
@@ -259,16 +482,17 @@ struct MachinePart {
 }
 
 impl MachinePart {
-    fn parse(input: &str) -> Self {
+    fn parse(input: &str) -> ParseResult<'_, Self> {
         input
-            .strip_prefix('{')
-            .unwrap()
-            .strip_suffix('}')
-            .unwrap()
+            .strip_prefix_or("{", "Expected opening brace")?
+            .strip_suffix_or("}", "Expected closing brace")?
             .split(',')
-            .fold(Self::default(), |part, field| {
-                let (name, amount) = field.split_once('=').unwrap();
-                part.set_field(Field::parse(name), amount.parse().unwrap())
+            .try_fold(Self::default(), |part, field| {
+                let (name, amount) = field.split_once_or("=", "Expected a rating")?;
+                Ok(part.set_field(
+                    Field::parse(name)?,
+                    amount.parse_or("Couldn't parse rating")?,
+                ))
             })
     }
 
@@ -296,12 +520,12 @@ impl MachinePart {
     }
 }
 
-#[derive(Debug, Clone)]
-struct MachinePartRange {
-    x: Range<u32>,
-    m: Range<u32>,
-    a: Range<u32>,
-    s: Range<u32>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachinePartRange {
+    pub x: Range<u32>,
+    pub m: Range<u32>,
+    pub a: Range<u32>,
+    pub s: Range<u32>,
 }
 
 impl Default for MachinePartRange {
@@ -325,7 +549,7 @@ impl MachinePartRange {
         }
         self
     }
-    fn get_field(&self, field: Field) -> &Range<u32> {
+    pub fn get_field(&self, field: Field) -> &Range<u32> {
         match field {
             Field::X => &self.x,
             Field::M => &self.m,
@@ -333,7 +557,7 @@ impl MachinePartRange {
             Field::S => &self.s,
         }
     }
-    fn rating(&self) -> usize {
+    pub fn rating(&self) -> usize {
         self.x.len() * self.m.len() * self.a.len() * self.s.len()
     }
 }
@@ -341,6 +565,7 @@ impl MachinePartRange {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"px{a<2006:qkq,m>2090:A,rfg}
 pv{a>1716:R,A}
@@ -366,7 +591,76 @@ hdj{m>838:A,pv}
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 353553);
+        let Some(input) = puzzle_input(19) else {
+            return;
+        };
+        assert_eq!(part1(&input), 353553);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("in{q<5:A,R}\n\n{x=1,m=2,a=3,s=4}").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (1, 4, "q"));
+        let error = try_part1("in{A}\n\n{x=1,m=2,a=3,s=4}\n{x=1,m=z,a=3,s=4}").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (4, 8, "z"));
+        let error = try_part1("in{x<5:a,R}\na{a}\n\n{x=1,m=2,a=3,s=4}").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.reason),
+            (2, 1, "Workflows form a cycle")
+        );
+        let error = try_part1("in{x<5:A,qq}\n\n{x=1,m=2,a=3,s=4}").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.reason),
+            (1, 10, "Unknown workflow")
+        );
+        let error = try_part1("in{x<5:A}\n\n{x=10,m=2,a=3,s=4}").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.reason),
+            (1, 8, "Last rule has a condition")
+        );
+        let error = try_part2("px{A}\n\n{x=1,m=2,a=3,s=4}").unwrap_err();
+        assert_eq!((error.line, error.reason), (1, "No in workflow"));
+    }
+    #[test]
+    fn analysis() {
+        let sorter = Day19::parse(TEST_INPUT).unwrap();
+        assert_eq!(sorter.analyse(), Analysis::default());
+        let input = "in{x<5:a,R}\na{m>3:b,qq}\nb{a}\nc{A}\n\n{x=1,m=2,a=3,s=4}";
+        let sorter = Sorter::parse_unchecked(input).unwrap();
+        assert_eq!(
+            sorter.analyse(),
+            Analysis {
+                unreachable: vec!["c"],
+                cycles: vec![vec!["a", "b"]],
+                undefined: vec![("a", "qq")],
+            }
+        );
+        assert!(sorter.accepted_paths().is_empty());
+        let dot = sorter.to_dot();
+        assert!(dot.contains("\"in\" -> \"a\" [label=\"x<5\"];"));
+        assert!(dot.contains("\"in\" -> \"R\";"));
+        assert!(dot.contains("\"a\" -> \"qq\" [style=\"dashed\"];"));
+    }
+    #[test]
+    fn accepted_paths() {
+        let sorter = Day19::parse(TEST_INPUT).unwrap();
+        let paths = sorter.accepted_paths();
+        let path = paths
+            .iter()
+            .find(|path| path.workflows == ["in", "px", "qkq"])
+            .unwrap();
+        assert_eq!(
+            path.part,
+            MachinePartRange {
+                x: 1..1416,
+                m: 1..4001,
+                a: 1..2006,
+                s: 1..1351
+            }
+        );
+        assert_eq!(
+            paths.iter().map(|path| path.part.rating()).sum::<usize>(),
+            167409079868000
+        );
     }
     #[test]
     fn test_input_part2() {
@@ -374,6 +668,9 @@ hdj{m>838:A,pv}
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 124615747767410);
+        let Some(input) = puzzle_input(19) else {
+            return;
+        };
+        assert_eq!(part2(&input), 124615747767410);
     }
 }
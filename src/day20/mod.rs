@@ -1,24 +1,126 @@
-use std::collections::HashMap;
+use num::Integer;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+};
 
+use crate::{
+    graph::Dot,
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day20;
+
+impl Solution for Day20 {
+    const DAY: u8 = 20;
+    type Parsed<'a> = Machine<'a>;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Machine::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(machine: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(machine.clone().calculate().into())
+    }
+    fn part2(machine: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        machine
+            .clone()
+            .initialization()
+            .map(Answer::from)
+            .map_err(SolveError::new)
+    }
+}
+
 pub fn part1(input: &str) -> usize {
-    Machine::parse(input).calculate()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    Machine::parse(input).initialization()
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+    Ok(Day20::parse(input)?.calculate())
 }
 
-struct Machine<'a> {
+pub fn try_part2(input: &str) -> Result<usize, MachineError> {
+    Day20::parse(input)?.initialization()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MachineError {
+    Parse(ParseError),
+    NoOutput,
+    OutputNotFedByConjunction,
+    NoFeeders {
+        conjunction: String,
+    },
+    NoCycle {
+        feeder: String,
+    },
+    IrregularCycle {
+        feeder: String,
+        first: usize,
+        period: usize,
+    },
+}
+
+impl From<ParseError> for MachineError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl fmt::Display for MachineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MachineError::Parse(error) => error.fmt(f),
+            MachineError::NoOutput => write!(f, "No module sends pulses to {}", Machine::OUTPUT),
+            MachineError::OutputNotFedByConjunction => write!(
+                f,
+                "Expected {} to be fed by a single conjunction",
+                Machine::OUTPUT
+            ),
+            MachineError::NoFeeders { conjunction } => {
+                write!(f, "Conjunction {conjunction} has no inputs")
+            }
+            MachineError::NoCycle { feeder } => write!(
+                f,
+                "{feeder} didn't send a repeating high pulse within {} presses",
+                Machine::MAX_PRESSES
+            ),
+            MachineError::IrregularCycle {
+                feeder,
+                first,
+                period,
+            } => write!(
+                f,
+                "{feeder} first sent a high pulse on press {first} but repeats every {period} presses"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MachineError {}
+
+#[derive(Clone)]
+pub struct Machine<'a> {
     modules: HashMap<&'a str, Module<'a>>,
 }
 
 impl<'a> Machine<'a> {
-    const PENULTIMATE_CONJUNCTION: &'static str = "qn";
-    fn parse(input: &'a str) -> Self {
-        let modules = input.split('\n').map(Module::parse).collect();
-        Self { modules }
+    const OUTPUT: &'static str = "rx";
+    const MAX_PRESSES: usize = 1 << 16;
+
+    fn parse(input: &'a str) -> ParseResult<'a, Self> {
+        let modules = input
+            .split('\n')
+            .map(Module::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { modules })
     }
 
     fn calculate(&mut self) -> usize {
@@ -30,60 +132,165 @@ impl<'a> Machine<'a> {
         high * low
     }
 
-    fn initialization(&mut self) -> usize {
+    fn initialization(&mut self) -> Result<usize, MachineError> {
         self.setup_conjunctions();
-        // This is a little bit cheeky since we use knowledge of the input here
-        let mut final_sources = HashMap::new();
-
-        for i in 1..5000 {
-            let mut pulses = vec![(Pulse::Low, "button", vec![Module::BROADCASTER])];
+        // rx receives a low pulse once every input of the conjunction feeding it is high.
+        // Each of those inputs is the output of a counter that resets when it fires.
+        let conjunction = self.output_conjunction()?;
+        let feeders = self.inputs(conjunction);
+        if feeders.is_empty() {
+            return Err(MachineError::NoFeeders {
+                conjunction: conjunction.to_owned(),
+            });
+        }
+        let mut high_presses: HashMap<_, Vec<usize>> =
+            feeders.iter().map(|feeder| (*feeder, vec![])).collect();
 
-            while let Some((pulse, source, destinations)) = pulses.pop() {
-                for destination in destinations {
-                    if destination == Self::PENULTIMATE_CONJUNCTION && matches!(pulse, Pulse::High)
-                    {
-                        final_sources.entry(source).or_insert(i);
-                    }
-                    let module = self.modules.get_mut(destination);
-                    if let Some((output, next_destinations)) =
-                        module.and_then(|m| m.handle_pulse(source, pulse))
-                    {
-                        pulses.push((output, destination, next_destinations));
-                    }
-                    if final_sources.len() == 4 {
-                        break;
+        for press in 1..=Self::MAX_PRESSES {
+            self.press(|source, pulse, destination| {
+                if destination == conjunction && pulse.is_high() {
+                    if let Some(presses) = high_presses.get_mut(source) {
+                        if presses.last() != Some(&press) {
+                            presses.push(press);
+                        }
                     }
                 }
+            });
+            if high_presses.values().all(|presses| presses.len() >= 2) {
+                break;
             }
         }
 
-        final_sources
-            .values()
-            .copied()
-            .reduce(num::integer::lcm)
-            .unwrap()
+        feeders
+            .into_iter()
+            .map(|feeder| match high_presses[feeder][..] {
+                [first, second, ..] if second - first == first => Ok(first),
+                [first, second, ..] => Err(MachineError::IrregularCycle {
+                    feeder: feeder.to_owned(),
+                    first,
+                    period: second - first,
+                }),
+                _ => Err(MachineError::NoCycle {
+                    feeder: feeder.to_owned(),
+                }),
+            })
+            .try_fold(1, |presses, cycle| Ok(presses.lcm(&cycle?)))
+    }
+
+    fn output_conjunction(&self) -> Result<&'a str, MachineError> {
+        match self.inputs(Self::OUTPUT)[..] {
+            [] => Err(MachineError::NoOutput),
+            [conjunction] if matches!(self.modules[conjunction], Module::Conjunction { .. }) => {
+                Ok(conjunction)
+            }
+            _ => Err(MachineError::OutputNotFedByConjunction),
+        }
+    }
+
+    fn inputs(&self, module: &str) -> Vec<&'a str> {
+        let mut inputs: Vec<_> = self
+            .modules
+            .iter()
+            .filter(|(_, other)| other.destinations().contains(&module))
+            .map(|(name, _)| *name)
+            .collect();
+        inputs.sort_unstable();
+        inputs
+    }
+
+    pub fn trace(&self, presses: usize) -> impl Iterator<Item = PulseEvent<'a>> + 'a {
+        let mut machine = self.clone();
+        machine.setup_conjunctions();
+        (1..=presses).flat_map(move |press| {
+            let mut events = vec![];
+            machine.press(|source, pulse, destination| {
+                events.push(PulseEvent {
+                    press,
+                    source,
+                    pulse,
+                    destination,
+                })
+            });
+            events
+        })
+    }
+
+    pub fn snapshots(&self, presses: usize) -> impl Iterator<Item = Snapshot<'a>> + 'a {
+        let mut machine = self.clone();
+        machine.setup_conjunctions();
+        (1..=presses).map(move |press| {
+            machine.press(|_, _, _| {});
+            machine.snapshot(press)
+        })
+    }
+
+    fn snapshot(&self, press: usize) -> Snapshot<'a> {
+        let mut snapshot = Snapshot {
+            press,
+            flip_flops: BTreeMap::new(),
+            conjunctions: BTreeMap::new(),
+        };
+        for (name, module) in &self.modules {
+            match module {
+                Module::Flipflop { state, .. } => {
+                    snapshot.flip_flops.insert(*name, *state);
+                }
+                Module::Conjunction { state, .. } => {
+                    snapshot.conjunctions.insert(*name, state.clone());
+                }
+                Module::Broadcaster { .. } => {}
+            }
+        }
+        snapshot
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new("modules");
+        let mut names: Vec<_> = self.modules.keys().copied().collect();
+        names.sort_unstable();
+        for name in &names {
+            let attributes: &[_] = match self.modules[name] {
+                Module::Broadcaster { .. } => &[("shape", "doublecircle")],
+                Module::Flipflop { .. } => &[("shape", "box"), ("color", "blue")],
+                Module::Conjunction { .. } => &[("shape", "diamond"), ("color", "red")],
+            };
+            dot.node(name, attributes);
+        }
+        for name in names {
+            for destination in self.modules[name].destinations() {
+                if !self.modules.contains_key(destination) {
+                    dot.node(destination, &[("shape", "plaintext")]);
+                }
+                dot.edge(name, destination, &[]);
+            }
+        }
+        dot.to_string()
     }
 
     fn send_pulse(&mut self) -> (usize, usize) {
         let mut high = 0;
         let mut low = 0;
-        let mut pulses = vec![(Pulse::Low, "button", vec![Module::BROADCASTER])];
+        self.press(|_, pulse, _| match pulse {
+            Pulse::High => high += 1,
+            Pulse::Low => low += 1,
+        });
+        (high, low)
+    }
 
-        while let Some((pulse, source, destinations)) = pulses.pop() {
-            match pulse {
-                Pulse::High => high += destinations.len(),
-                Pulse::Low => low += destinations.len(),
-            };
+    fn press(&mut self, mut observe: impl FnMut(&'a str, Pulse, &'a str)) {
+        let mut pulses = VecDeque::from([(Pulse::Low, "button", vec![Module::BROADCASTER])]);
+
+        while let Some((pulse, source, destinations)) = pulses.pop_front() {
             for destination in destinations {
+                observe(source, pulse, destination);
                 let module = self.modules.get_mut(destination);
                 if let Some((output, next_destinations)) =
                     module.and_then(|m| m.handle_pulse(source, pulse))
                 {
-                    pulses.push((output, destination, next_destinations));
+                    pulses.push_back((output, destination, next_destinations));
                 }
             }
         }
-        (high, low)
     }
 
     fn setup_conjunctions(&mut self) {
@@ -91,15 +298,7 @@ impl<'a> Machine<'a> {
             .modules
             .iter()
             .filter(|(_, module)| matches!(module, Module::Conjunction { .. }))
-            .map(|(conjunction, _)| {
-                let inputs: Vec<_> = self
-                    .modules
-                    .iter()
-                    .filter(|(_, module)| module.destinations().contains(conjunction))
-                    .map(|(name, _)| *name)
-                    .collect();
-                (*conjunction, inputs)
-            })
+            .map(|(conjunction, _)| (*conjunction, self.inputs(conjunction)))
             .collect();
 
         conjunctions.into_iter().for_each(|(conjunction, inputs)| {
@@ -112,6 +311,23 @@ impl<'a> Machine<'a> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PulseEvent<'a> {
+    pub press: usize,
+    pub source: &'a str,
+    pub pulse: Pulse,
+    pub destination: &'a str,
+}
+
+// The state of every flip-flop and conjunction once a press has settled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot<'a> {
+    pub press: usize,
+    pub flip_flops: BTreeMap<&'a str, FlipFlopState>,
+    pub conjunctions: BTreeMap<&'a str, ConjunctionState<'a>>,
+}
+
+#[derive(Clone)]
 enum Module<'a> {
     Broadcaster {
         destinations: Vec<&'a str>,
@@ -128,27 +344,30 @@ enum Module<'a> {
 
 impl<'a> Module<'a> {
     const BROADCASTER: &'static str = "broadcaster";
-    fn parse(input: &'a str) -> (&'a str, Self) {
-        let (type_name, destinations) = input.split_once(" -> ").unwrap();
+    fn parse(input: &'a str) -> ParseResult<'a, (&'a str, Self)> {
+        let (type_name, destinations) = input.split_once_or(" -> ", "Expected destinations")?;
         let destinations = destinations.split(", ").collect();
 
-        match type_name.chars().next().unwrap() {
-            '%' => (
-                &type_name[1..],
+        if let Some(name) = type_name.strip_prefix('%') {
+            Ok((
+                name,
                 Self::Flipflop {
                     state: Default::default(),
                     destinations,
                 },
-            ),
-            '&' => (
-                &type_name[1..],
+            ))
+        } else if let Some(name) = type_name.strip_prefix('&') {
+            Ok((
+                name,
                 Self::Conjunction {
                     state: Default::default(),
                     destinations,
                 },
-            ),
-            'b' => (Self::BROADCASTER, Self::Broadcaster { destinations }),
-            _ => panic!("Unexpected input"),
+            ))
+        } else if type_name == Self::BROADCASTER {
+            Ok((Self::BROADCASTER, Self::Broadcaster { destinations }))
+        } else {
+            Err(type_name.invalid("Unexpected module type"))
         }
     }
 
@@ -190,8 +409,8 @@ impl<'a> Module<'a> {
     }
 }
 
-#[derive(Default, Clone, Copy)]
-enum FlipFlopState {
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FlipFlopState {
     On,
     #[default]
     Off,
@@ -211,12 +430,16 @@ impl FlipFlopState {
         }
     }
 }
-#[derive(Default)]
-struct ConjunctionState<'a> {
-    inputs: HashMap<&'a str, Pulse>,
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConjunctionState<'a> {
+    inputs: BTreeMap<&'a str, Pulse>,
 }
 
 impl<'a> ConjunctionState<'a> {
+    pub fn inputs(&self) -> &BTreeMap<&'a str, Pulse> {
+        &self.inputs
+    }
+
     fn output(&mut self, source: &'a str, pulse: Pulse) -> Pulse {
         self.inputs.insert(source, pulse);
         if self.inputs.values().all(Pulse::is_high) {
@@ -227,14 +450,14 @@ impl<'a> ConjunctionState<'a> {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-enum Pulse {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pulse {
     High,
     Low,
 }
 
 impl Pulse {
-    fn is_high(&self) -> bool {
+    pub fn is_high(&self) -> bool {
         matches!(self, Pulse::High)
     }
 }
@@ -242,6 +465,7 @@ impl Pulse {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{input::puzzle_input, solution};
 
     const TEST_INPUT_1: &str = r#"broadcaster -> a, b, c
 %a -> b
@@ -261,11 +485,119 @@ mod test {
         assert_eq!(part1(TEST_INPUT_2), 11687500);
     }
     #[test]
+    fn pulses_are_processed_in_order() {
+        // c receives pulses from both a and b during a press, so handling the newest pulse
+        // first gives 2500 high and 4500 low pulses instead
+        let input = "broadcaster -> a, b\n%a -> c\n&b -> c\n&c -> out";
+        assert_eq!(part1(input), 2501 * 4499);
+    }
+    #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 925955316);
+        let Some(input) = puzzle_input(20) else {
+            return;
+        };
+        assert_eq!(part1(&input), 925955316);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("broadcaster -> a\n*a -> b").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.text.as_str()),
+            (2, 1, "*a")
+        );
+    }
+    #[test]
+    fn trace() {
+        let machine = Day20::parse(TEST_INPUT_1).unwrap();
+        let events: Vec<_> = machine.trace(2).collect();
+        assert_eq!(events.len(), 24);
+        assert_eq!(
+            events[0],
+            PulseEvent {
+                press: 1,
+                source: "button",
+                pulse: Pulse::Low,
+                destination: "broadcaster"
+            }
+        );
+        let first_press: Vec<_> = events[..12]
+            .iter()
+            .map(|event| (event.source, event.pulse, event.destination))
+            .collect();
+        use Pulse::{High, Low};
+        assert_eq!(
+            first_press,
+            [
+                ("button", Low, "broadcaster"),
+                ("broadcaster", Low, "a"),
+                ("broadcaster", Low, "b"),
+                ("broadcaster", Low, "c"),
+                ("a", High, "b"),
+                ("b", High, "c"),
+                ("c", High, "inv"),
+                ("inv", Low, "a"),
+                ("a", Low, "b"),
+                ("b", Low, "c"),
+                ("c", Low, "inv"),
+                ("inv", High, "a"),
+            ]
+        );
+        assert_eq!(events[12].press, 2);
+        let high = events.iter().filter(|event| event.pulse.is_high()).count();
+        assert_eq!(high, 8);
+    }
+    #[test]
+    fn snapshots() {
+        let machine = Day20::parse(TEST_INPUT_2).unwrap();
+        let flip_flops: Vec<_> = machine
+            .snapshots(4)
+            .map(|snapshot| (snapshot.flip_flops["a"], snapshot.flip_flops["b"]))
+            .collect();
+        use FlipFlopState::{Off, On};
+        assert_eq!(flip_flops, [(On, On), (Off, On), (On, Off), (Off, Off)]);
+        let snapshot = machine.snapshots(1).next().unwrap();
+        assert_eq!(
+            snapshot.conjunctions["con"].inputs(),
+            &BTreeMap::from([("a", Pulse::High), ("b", Pulse::High)])
+        );
+    }
+    #[test]
+    fn dot() {
+        let dot = Day20::parse(TEST_INPUT_2).unwrap().to_dot();
+        assert!(dot.contains("\"a\" [shape=\"box\", color=\"blue\"];"));
+        assert!(dot.contains("\"con\" [shape=\"diamond\", color=\"red\"];"));
+        assert!(dot.contains("\"output\" [shape=\"plaintext\"];"));
+        assert!(dot.contains("\"con\" -> \"output\";"));
+    }
+    #[test]
+    fn counter_structure() {
+        let input = "broadcaster -> a, b
+%a -> ia
+&ia -> hub
+%b -> c
+%c -> ib
+&ib -> hub
+&hub -> rx";
+        assert_eq!(try_part2(input), Ok(4));
+        assert_eq!(try_part2(TEST_INPUT_2), Err(MachineError::NoOutput));
+        let error = solution::day(20)
+            .unwrap()
+            .solve(TEST_INPUT_2, solution::Part::Two)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Day 20 part 2: No module sends pulses to rx"
+        );
+        assert_eq!(
+            try_part2("broadcaster -> a\n%a -> rx"),
+            Err(MachineError::OutputNotFedByConjunction)
+        );
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 241528477694627);
+        let Some(input) = puzzle_input(20) else {
+            return;
+        };
+        assert_eq!(part2(&input), 241528477694627);
     }
 }
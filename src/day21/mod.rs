@@ -1,17 +1,60 @@
-use std::ops::{AddAssign, BitAnd, BitOr, BitOrAssign, Shl, Shr, Sub};
+use ethnum::U256;
+use num::Integer;
+use std::{
+    collections::HashMap,
+    ops::{AddAssign, BitAnd, BitOr, BitOrAssign, Shl, Shr, Sub},
+};
 
+use crate::{
+    geometry::Position,
+    grid::Grid,
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day21;
+
+impl Solution for Day21 {
+    const DAY: u8 = 21;
+    type Parsed<'a> = Garden<U256>;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Garden::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(garden: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(garden.plots_part1(64).into())
+    }
+    fn part2(garden: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(garden.plots_part2(26501365).into())
+    }
+}
+
 pub fn part1<T: Number>(input: &str, steps: usize) -> usize {
-    Garden::<T>::parse(input).plots_part1(steps)
+    try_part1::<T>(input, steps).unwrap()
 }
 
 pub fn part2<T: Number>(input: &str, steps: usize) -> usize {
-    Garden::<T>::parse(input).plots_part2(steps)
+    try_part2::<T>(input, steps).unwrap()
+}
+
+pub fn try_part1<T: Number>(input: &str, steps: usize) -> Result<usize, ParseError> {
+    Ok(Garden::<T>::parse(input)
+        .map_err(|error| error.locate(Day21::DAY, input))?
+        .plots_part1(steps))
 }
 
-struct Garden<T> {
+pub fn try_part2<T: Number>(input: &str, steps: usize) -> Result<usize, ParseError> {
+    Ok(Garden::<T>::parse(input)
+        .map_err(|error| error.locate(Day21::DAY, input))?
+        .plots_part2(steps))
+}
+
+pub struct Garden<T> {
     rows: Vec<T>,
+    width: usize,
     mask: T,
     starting_position: (usize, usize),
 }
@@ -20,32 +63,36 @@ impl<T> Garden<T>
 where
     T: Number,
 {
-    fn parse(input: &str) -> Self {
-        let mut rows = vec![];
-        let mut starting_position = None;
-        for (row_index, line) in input.split('\n').enumerate() {
-            let mut row = T::zero();
-            for (i, c) in line.chars().enumerate() {
-                let (val, includes_s) = match c {
-                    '.' => (T::one(), false),
-                    '#' => (T::zero(), false),
-                    'S' => (T::one(), true),
-                    _ => panic!("Unexpected symbol in input"),
-                };
-                row += val << i;
-                if includes_s {
-                    starting_position = Some((row_index, i));
-                }
-            }
-            rows.push(row);
-        }
-        let mask = (T::one() << (rows.len())) - T::one();
+    // Number of consecutive repeats of the second difference before extrapolating
+    const STABLE_CYCLES: usize = 4;
 
-        Self {
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let grid = Grid::parse(
+            input,
+            |c| matches!(c, '.' | '#' | 'S').then_some(c),
+            "Unexpected symbol in input",
+        )?;
+        let Position(x, y) = grid
+            .find(|c| *c == 'S')
+            .ok_or_else(|| input.invalid("No starting position"))?;
+        let rows = grid
+            .rows()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c != '#')
+                    .fold(T::zero(), |row, (i, _)| row | (T::one() << i))
+            })
+            .collect();
+        let width = grid.width();
+        let mask = (T::one() << width) - T::one();
+
+        Ok(Self {
             rows,
-            starting_position: starting_position.unwrap(),
+            width,
+            starting_position: (y, x),
             mask,
-        }
+        })
     }
 
     fn plots_part1(&self, steps: usize) -> usize {
@@ -54,40 +101,34 @@ where
     }
 
     fn plots_part2(&self, steps: usize) -> usize {
-        let result = self.plots(steps, self.starting_position);
-        dbg!(&result);
-        let mut plots = result.total_plots_reached;
-        if let Some(top_exit) = result.top {
-            let result = self.plots(
-                steps - top_exit.step - 1,
-                (self.rows.len() - 1, top_exit.location),
-            );
-            plots += result.total_plots_reached;
-        }
-        if let Some(bottom_exit) = result.bottom {
-            let result = self.plots(
-                dbg!(steps - bottom_exit.step - 1),
-                (0, bottom_exit.location),
-            );
-            dbg!(&result);
-            plots += result.total_plots_reached;
-        }
-        if let Some(left_exit) = result.left {
-            let result = self.plots(
-                steps - left_exit.step - 1,
-                (left_exit.location, self.mask.count_ones() as usize - 1),
-            );
-            plots += result.total_plots_reached;
-        }
-        if let Some(right_exit) = result.right {
-            let result = self.plots(steps - right_exit.step - 1, (right_exit.location, 0));
-            plots += result.total_plots_reached;
+        // Once the garden has been crossed a few times the reachable region grows by the
+        // same shape every period, so the plots reached each period are quadratic.
+        let period = self.width.lcm(&self.rows.len());
+        let mut tiles = self.start_tiles();
+        let mut samples = vec![];
+        let mut stable = 0;
+        for step in 0..steps {
+            if step % period == steps % period {
+                samples.push(Self::count(&tiles) as i128);
+                if let [.., a, b, c] = samples[..] {
+                    let difference = c - 2 * b + a;
+                    stable = match samples[..] {
+                        [.., z, a, b, _] if b - 2 * a + z == difference => stable + 1,
+                        _ => 0,
+                    };
+                    if stable == Self::STABLE_CYCLES {
+                        let cycles = ((steps - step) / period) as i128;
+                        return (c + cycles * (c - b) + difference * cycles * (cycles + 1) / 2)
+                            as usize;
+                    }
+                }
+            }
+            tiles = self.step_tiles(&tiles);
         }
-
-        plots
+        Self::count(&tiles)
     }
 
-    fn plots(&self, steps: usize, (row, column): (usize, usize)) -> PlotResult {
+    fn plots(&self, steps: usize, (row, column): (usize, usize)) -> PlotResult<T> {
         let mut result = PlotResult::default();
         let mut current_rows: Vec<_> = (0..self.rows.len())
             .map(|i| {
@@ -99,102 +140,155 @@ where
             })
             .collect();
         let mut next_rows = current_rows.to_owned();
-        for step in 0..steps {
-            next_rows.iter_mut().for_each(|val| *val = T::zero());
-            current_rows
-                .iter()
-                .copied()
-                .enumerate()
-                .for_each(|(index, val)| {
-                    if index > 0 {
-                        next_rows[index - 1] |= val & self.rows[index - 1];
-                    } else if val > T::zero() {
-                        result.set_top_exit(val, step);
-                    }
-                    if val & T::one() == T::one() {
-                        result.set_left_exit(index, step);
-                    }
-                    if (val << 1) & self.mask < val {
-                        result.set_right_exit(index, step);
-                    }
-                    next_rows[index] |= self.next(val) & self.rows[index];
-                    if index < next_rows.len() - 1 {
-                        next_rows[index + 1] |= val & self.rows[index + 1];
-                    } else if val > T::zero() {
-                        result.set_bottom_exit(val, step);
-                    }
-                });
+        for _ in 0..steps {
+            result = self.step(&current_rows, &mut next_rows);
             std::mem::swap(&mut current_rows, &mut next_rows);
-            //dbg!(current_rows.iter().copied().map(T::count_ones).sum::<u32>());
         }
         result.set_plot_reached(current_rows.into_iter().map(T::count_ones).sum::<u32>() as usize);
         result
     }
 
+    fn start_tiles(&self) -> HashMap<(isize, isize), Vec<T>> {
+        let (row, column) = self.starting_position;
+        let mut start = vec![T::zero(); self.rows.len()];
+        start[row] = T::one() << column;
+        HashMap::from([((0, 0), start)])
+    }
+
+    fn step_tiles(
+        &self,
+        tiles: &HashMap<(isize, isize), Vec<T>>,
+    ) -> HashMap<(isize, isize), Vec<T>> {
+        let height = self.rows.len();
+        let top_column = T::one() << (self.width - 1);
+        let mut next_tiles = HashMap::with_capacity(tiles.len());
+        let mut next_rows = vec![T::zero(); height];
+        for (&(x, y), rows) in tiles {
+            let exits = self.step(rows, &mut next_rows);
+            self.enter(&mut next_tiles, (x, y), |tile| {
+                tile.iter_mut()
+                    .zip(&next_rows)
+                    .for_each(|(tile, next)| *tile |= *next)
+            });
+            if exits.top > T::zero() {
+                self.enter(&mut next_tiles, (x, y - 1), |tile| {
+                    tile[height - 1] |= exits.top & self.rows[height - 1]
+                });
+            }
+            if exits.bottom > T::zero() {
+                self.enter(&mut next_tiles, (x, y + 1), |tile| {
+                    tile[0] |= exits.bottom & self.rows[0]
+                });
+            }
+            if exits.left > T::zero() {
+                self.enter(&mut next_tiles, (x - 1, y), |tile| {
+                    Self::rows_of(exits.left, height)
+                        .for_each(|row| tile[row] |= top_column & self.rows[row])
+                });
+            }
+            if exits.right > T::zero() {
+                self.enter(&mut next_tiles, (x + 1, y), |tile| {
+                    Self::rows_of(exits.right, height)
+                        .for_each(|row| tile[row] |= T::one() & self.rows[row])
+                });
+            }
+        }
+        next_tiles
+    }
+
+    fn enter(
+        &self,
+        tiles: &mut HashMap<(isize, isize), Vec<T>>,
+        tile: (isize, isize),
+        update: impl FnOnce(&mut Vec<T>),
+    ) {
+        update(
+            tiles
+                .entry(tile)
+                .or_insert_with(|| vec![T::zero(); self.rows.len()]),
+        )
+    }
+
+    fn rows_of(rows: T, height: usize) -> impl Iterator<Item = usize> {
+        (0..height).filter(move |row| (rows >> *row) & T::one() == T::one())
+    }
+
+    fn count(tiles: &HashMap<(isize, isize), Vec<T>>) -> usize {
+        tiles
+            .values()
+            .flatten()
+            .map(|row| row.count_ones() as usize)
+            .sum()
+    }
+
+    fn step(&self, current_rows: &[T], next_rows: &mut [T]) -> PlotResult<T> {
+        let mut result = PlotResult::default();
+        next_rows.iter_mut().for_each(|val| *val = T::zero());
+        current_rows
+            .iter()
+            .copied()
+            .enumerate()
+            .for_each(|(index, val)| {
+                if index > 0 {
+                    next_rows[index - 1] |= val & self.rows[index - 1];
+                } else {
+                    result.top = val;
+                }
+                if val & T::one() == T::one() {
+                    result.set_left_exit(index);
+                }
+                if (val << 1) & self.mask < val {
+                    result.set_right_exit(index);
+                }
+                next_rows[index] |= self.next(val) & self.rows[index];
+                if index < next_rows.len() - 1 {
+                    next_rows[index + 1] |= val & self.rows[index + 1];
+                } else {
+                    result.bottom = val;
+                }
+            });
+        result
+    }
+
     fn next(&self, current: T) -> T {
         ((current << 1) | (current >> 1)) & self.mask
     }
 }
 
-#[derive(Default, Debug)]
-struct PlotResult {
-    top: Option<Exit>,
-    bottom: Option<Exit>,
-    left: Option<Exit>,
-    right: Option<Exit>,
+// The positions leaving the garden through each edge, the left and right exits are indexed by row
+#[derive(Debug)]
+struct PlotResult<T> {
+    top: T,
+    bottom: T,
+    left: T,
+    right: T,
     total_plots_reached: usize,
 }
 
-impl PlotResult {
-    fn set_top_exit<T: Number>(&mut self, val: T, step: usize) {
-        if self.top.is_none() {
-            assert!(val.count_ones() == 1);
-            self.top = Some(Exit {
-                location: val.trailing_zeros() as usize,
-                step,
-            });
-        }
-    }
-    fn set_bottom_exit<T: Number>(&mut self, val: T, step: usize) {
-        if self.bottom.is_none() {
-            assert!(val.count_ones() == 1);
-            self.bottom = Some(Exit {
-                location: val.trailing_zeros() as usize,
-                step,
-            });
+impl<T: Number> Default for PlotResult<T> {
+    fn default() -> Self {
+        Self {
+            top: T::zero(),
+            bottom: T::zero(),
+            left: T::zero(),
+            right: T::zero(),
+            total_plots_reached: 0,
         }
     }
-    fn set_left_exit(&mut self, index: usize, step: usize) {
-        if self.left.is_none() {
-            self.left = Some(Exit {
-                location: index,
-                step,
-            });
-        } else {
-            assert!(self.left.as_ref().unwrap().step < step);
-        }
+}
+
+impl<T: Number> PlotResult<T> {
+    fn set_left_exit(&mut self, index: usize) {
+        self.left |= T::one() << index;
     }
-    fn set_right_exit(&mut self, index: usize, step: usize) {
-        if self.right.is_none() {
-            self.right = Some(Exit {
-                location: index,
-                step,
-            });
-        } else {
-            assert!(self.right.as_ref().unwrap().step < step);
-        }
+    fn set_right_exit(&mut self, index: usize) {
+        self.right |= T::one() << index;
     }
     fn set_plot_reached(&mut self, plots: usize) {
         self.total_plots_reached = plots;
     }
 }
 
-#[derive(Debug)]
-struct Exit {
-    location: usize,
-    step: usize,
-}
-
 pub trait Number:
     Sized
     + Copy
@@ -233,28 +327,28 @@ impl Number for u16 {
     }
 }
 
-#[cfg(test)]
-mod test {
-    use ethnum::U256;
-    impl Number for U256 {
-        fn one() -> Self {
-            U256::ONE
-        }
+impl Number for U256 {
+    fn one() -> Self {
+        U256::ONE
+    }
 
-        fn zero() -> Self {
-            U256::ZERO
-        }
+    fn zero() -> Self {
+        U256::ZERO
+    }
 
-        fn count_ones(self) -> u32 {
-            U256::count_ones(self)
-        }
+    fn count_ones(self) -> u32 {
+        U256::count_ones(self)
+    }
 
-        fn trailing_zeros(self) -> u32 {
-            U256::trailing_zeros(self)
-        }
+    fn trailing_zeros(self) -> u32 {
+        U256::trailing_zeros(self)
     }
+}
 
+#[cfg(test)]
+mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"...........
 .....###.#.
@@ -274,7 +368,17 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1::<U256>(PUZZLE_INPUT, 64), 3632);
+        let Some(input) = puzzle_input(21) else {
+            return;
+        };
+        assert_eq!(part1::<U256>(&input, 64), 3632);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part1::<u16>("..#\n.S*", 1).unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 3, "*"));
+        let error = try_part1::<u16>("..#\n...", 1).unwrap_err();
+        assert_eq!(error.reason, "No starting position");
     }
     #[test]
     fn test_input_part2() {
@@ -287,6 +391,42 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part2() {
-        // assert_eq!(part2::<U256>(PUZZLE_INPUT, 26501365), 0);
+        let Some(input) = puzzle_input(21) else {
+            return;
+        };
+        assert_eq!(part2::<U256>(&input, 26501365), 600336060511101);
+    }
+    #[test]
+    fn matches_brute_force() {
+        for steps in [0, 1, 5, 6, 11, 17, 40, 64, 101] {
+            assert_eq!(
+                part2::<u16>(TEST_INPUT, steps),
+                brute_force(TEST_INPUT, steps)
+            );
+            assert_eq!(
+                part2::<U256>(TEST_INPUT, steps),
+                brute_force(TEST_INPUT, steps)
+            );
+        }
+    }
+
+    fn brute_force(input: &str, steps: usize) -> usize {
+        let grid: Vec<&[u8]> = input.split('\n').map(str::as_bytes).collect();
+        let (height, width) = (grid.len() as isize, grid[0].len() as isize);
+        let start = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .find(|(x, y)| grid[*y as usize][*x as usize] == b'S')
+            .unwrap();
+        let mut reached = std::collections::HashSet::from([start]);
+        for _ in 0..steps {
+            reached = reached
+                .iter()
+                .flat_map(|(x, y)| [(x - 1, *y), (x + 1, *y), (*x, y - 1), (*x, y + 1)])
+                .filter(|(x, y)| {
+                    grid[y.rem_euclid(height) as usize][x.rem_euclid(width) as usize] != b'#'
+                })
+                .collect();
+        }
+        reached.len()
     }
 }
@@ -1,62 +1,187 @@
-use std::str::FromStr;
+use crate::{
+    parse::{ParseError, ParseResult, ParseStr},
+    solution::{Answer, Solution, SolveError},
+};
 
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct Day22;
+
+impl Solution for Day22 {
+    const DAY: u8 = 22;
+    type Parsed<'a> = SettledStack;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Stack::parse(input)
+            .map(Stack::settle)
+            .map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(stack: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(stack.disintegratable().into())
+    }
+    fn part2(stack: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(stack.chain_reactions().into())
+    }
+}
+
 pub fn part1(input: &str) -> usize {
-    Stack::parse(input).disintegratable()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    todo!();
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+    Ok(Day22::parse(input)?.disintegratable())
 }
 
-#[derive(Debug)]
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+    Ok(Day22::parse(input)?.chain_reactions())
+}
+
+#[derive(Debug, Clone)]
 struct Stack {
     blocks: Vec<Block>,
 }
 
 impl Stack {
-    fn parse(input: &str) -> Self {
-        let blocks = input.split('\n').map(Block::parse).collect();
-        Self { blocks }
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let blocks = input
+            .split('\n')
+            .map(Block::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { blocks })
+    }
+
+    fn settle(mut self) -> SettledStack {
+        self.blocks.sort_by_key(Block::min_z);
+        let width = self.blocks.iter().map(Block::max_x).max().unwrap_or(0) as usize + 1;
+        let depth = self.blocks.iter().map(Block::max_y).max().unwrap_or(0) as usize + 1;
+        // The top of the highest block over each cell along with the index of that block
+        let mut height_map: Vec<Option<(u32, usize)>> = vec![None; width * depth];
+        let mut supported_by = Vec::with_capacity(self.blocks.len());
+
+        for (index, block) in self.blocks.iter().enumerate() {
+            let footprint: Vec<_> = block.footprint(width).collect();
+            let resting_height = footprint
+                .iter()
+                .filter_map(|cell| height_map[*cell])
+                .map(|(top, _)| top)
+                .max()
+                .unwrap_or(0);
+            let mut below: Vec<_> = footprint
+                .iter()
+                .filter_map(|cell| height_map[*cell])
+                .filter(|(top, _)| *top == resting_height)
+                .map(|(_, block)| block)
+                .collect();
+            below.sort_unstable();
+            below.dedup();
+
+            let top = resting_height + block.height();
+            footprint
+                .into_iter()
+                .for_each(|cell| height_map[cell] = Some((top, index)));
+            supported_by.push(below);
+        }
+
+        SettledStack::new(supported_by)
+    }
+}
+
+pub struct SettledStack {
+    supports: Vec<Vec<usize>>,
+    supported_by: Vec<Vec<usize>>,
+}
+
+impl SettledStack {
+    fn new(supported_by: Vec<Vec<usize>>) -> Self {
+        let mut supports = vec![vec![]; supported_by.len()];
+        for (block, below) in supported_by.iter().enumerate() {
+            below
+                .iter()
+                .for_each(|support| supports[*support].push(block));
+        }
+        Self {
+            supports,
+            supported_by,
+        }
     }
 
-    fn disintegratable(&mut self) -> usize {
-        self.settle();
-        dbg!(&self);
-        self.calculate_disintigratable()
+    fn disintegratable(&self) -> usize {
+        self.supports
+            .iter()
+            .filter(|above| {
+                above
+                    .iter()
+                    .all(|block| self.supported_by[*block].len() > 1)
+            })
+            .count()
     }
 
-    fn settle(&mut self) {
-        self.blocks.sort_by_key(|block_1| block_1.min_z());
+    fn chain_reactions(&self) -> usize {
+        (0..self.supported_by.len())
+            .map(|block| self.falling(block))
+            .sum()
     }
 
-    fn calculate_disintigratable(&self) -> usize {
-        todo!()
+    // Blocks are ordered by height once settled, so every block's supports come before it
+    fn falling(&self, removed: usize) -> usize {
+        let mut falling = vec![false; self.supported_by.len()];
+        falling[removed] = true;
+        let mut count = 0;
+        for block in (removed + 1)..self.supported_by.len() {
+            let below = &self.supported_by[block];
+            if !below.is_empty() && below.iter().all(|support| falling[*support]) {
+                falling[block] = true;
+                count += 1;
+            }
+        }
+        count
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Block {
     start: Coordinate,
     end: Coordinate,
 }
 
 impl Block {
-    fn parse(input: &str) -> Self {
-        let (start, end) = input.split_once('~').unwrap();
-        Self {
-            start: Coordinate::parse(start),
-            end: Coordinate::parse(end),
-        }
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let (start, end) = input.split_once_or("~", "Expected two coordinates")?;
+        Ok(Self {
+            start: Coordinate::parse(start)?,
+            end: Coordinate::parse(end)?,
+        })
     }
 
     fn min_z(&self) -> u32 {
         std::cmp::min(self.start.z, self.end.z)
     }
+
+    fn max_x(&self) -> u32 {
+        std::cmp::max(self.start.x, self.end.x)
+    }
+
+    fn max_y(&self) -> u32 {
+        std::cmp::max(self.start.y, self.end.y)
+    }
+
+    fn height(&self) -> u32 {
+        self.start.z.abs_diff(self.end.z) + 1
+    }
+
+    fn footprint(&self, width: usize) -> impl Iterator<Item = usize> {
+        let xs = std::cmp::min(self.start.x, self.end.x) as usize..=self.max_x() as usize;
+        let ys = std::cmp::min(self.start.y, self.end.y) as usize..=self.max_y() as usize;
+        ys.flat_map(move |y| xs.clone().map(move |x| y * width + x))
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Coordinate {
     x: u32,
     y: u32,
@@ -64,18 +189,22 @@ struct Coordinate {
 }
 
 impl Coordinate {
-    fn parse(input: &str) -> Self {
-        let mut coordinates = input.split(',').map(FromStr::from_str).map(Result::unwrap);
-        let x = coordinates.next().unwrap();
-        let y = coordinates.next().unwrap();
-        let z = coordinates.next().unwrap();
-        Self { x, y, z }
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        let coordinates = input
+            .split(',')
+            .map(|coordinate| coordinate.parse_or("Couldn't parse coordinate"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [x, y, z] = coordinates[..] else {
+            return Err(input.invalid("Expected three coordinates"));
+        };
+        Ok(Self { x, y, z })
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#"1,0,1~1,2,1
 0,0,2~2,0,2
@@ -91,14 +220,30 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 0);
+        let Some(input) = puzzle_input(22) else {
+            return;
+        };
+        assert_eq!(part1(&input), 5);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("1,0,1~1,2,1\n0,0,2~2,0").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.text.as_str()),
+            (2, 7, "2,0")
+        );
+        let error = try_part1("1,0,1~1,a,1").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (1, 9, "a"));
     }
     #[test]
     fn test_input_part2() {
-        assert_eq!(part2(TEST_INPUT), 0);
+        assert_eq!(part2(TEST_INPUT), 7);
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 0);
+        let Some(input) = puzzle_input(22) else {
+            return;
+        };
+        assert_eq!(part2(&input), 7);
     }
 }
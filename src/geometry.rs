@@ -0,0 +1,155 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    pub fn reverse(self) -> Self {
+        Self::ALL[(self as usize + 2) % 4]
+    }
+
+    pub fn left(self) -> Self {
+        Self::ALL[(self as usize + 3) % 4]
+    }
+
+    pub fn right(self) -> Self {
+        Self::ALL[(self as usize + 1) % 4]
+    }
+
+    // North is towards smaller y, matching the order rows appear in the input
+    pub fn offset(self) -> Point {
+        match self {
+            Self::North => Point(0, -1),
+            Self::East => Point(1, 0),
+            Self::South => Point(0, 1),
+            Self::West => Point(-1, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Position(pub usize, pub usize);
+
+impl Position {
+    pub fn checked_move(self, direction: Direction) -> Option<Self> {
+        self.checked_move_by(direction, 1)
+    }
+
+    pub fn checked_move_by(self, direction: Direction, distance: usize) -> Option<Self> {
+        let Self(x, y) = self;
+        Some(match direction {
+            Direction::North => Self(x, y.checked_sub(distance)?),
+            Direction::East => Self(x.checked_add(distance)?, y),
+            Direction::South => Self(x, y.checked_add(distance)?),
+            Direction::West => Self(x.checked_sub(distance)?, y),
+        })
+    }
+
+    pub fn neighbours(self) -> impl Iterator<Item = (Direction, Self)> {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |direction| Some((direction, self.checked_move(direction)?)))
+    }
+
+    pub fn surrounding(self) -> impl Iterator<Item = Self> {
+        Point::from(self)
+            .surrounding()
+            .filter_map(|point| point.try_into().ok())
+    }
+
+    pub fn manhattan(self, Self(x, y): Self) -> usize {
+        self.0.abs_diff(x) + self.1.abs_diff(y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Point(pub isize, pub isize);
+
+impl Point {
+    pub fn moved(self, direction: Direction, distance: isize) -> Self {
+        let Self(dx, dy) = direction.offset();
+        Self(self.0 + dx * distance, self.1 + dy * distance)
+    }
+
+    pub fn checked_moved(self, direction: Direction, distance: isize) -> Option<Self> {
+        let Self(dx, dy) = direction.offset();
+        Some(Self(
+            self.0.checked_add(dx.checked_mul(distance)?)?,
+            self.1.checked_add(dy.checked_mul(distance)?)?,
+        ))
+    }
+
+    pub fn neighbours(self) -> impl Iterator<Item = (Direction, Self)> {
+        Direction::ALL
+            .into_iter()
+            .map(move |direction| (direction, self.moved(direction, 1)))
+    }
+
+    pub fn surrounding(self) -> impl Iterator<Item = Self> {
+        let Self(x, y) = self;
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| Self(x + dx, y + dy)))
+            .filter(move |point| *point != self)
+    }
+
+    pub fn manhattan(self, Self(x, y): Self) -> usize {
+        self.0.abs_diff(x) + self.1.abs_diff(y)
+    }
+}
+
+impl From<Position> for Point {
+    fn from(Position(x, y): Position) -> Self {
+        Self(x as isize, y as isize)
+    }
+}
+
+impl TryFrom<Point> for Position {
+    type Error = Point;
+
+    fn try_from(point @ Point(x, y): Point) -> Result<Self, Self::Error> {
+        match (usize::try_from(x), usize::try_from(y)) {
+            (Ok(x), Ok(y)) => Ok(Self(x, y)),
+            _ => Err(point),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn directions() {
+        assert_eq!(Direction::North.reverse(), Direction::South);
+        assert_eq!(Direction::North.left(), Direction::West);
+        assert_eq!(Direction::West.right(), Direction::North);
+        assert!(Direction::ALL
+            .iter()
+            .all(|d| d.left().right() == *d && d.right().right() == d.reverse()));
+    }
+    #[test]
+    fn positions() {
+        let origin = Position(0, 0);
+        assert_eq!(origin.checked_move(Direction::North), None);
+        assert_eq!(origin.checked_move(Direction::East), Some(Position(1, 0)));
+        assert_eq!(origin.neighbours().count(), 2);
+        assert_eq!(origin.surrounding().count(), 3);
+        assert_eq!(Position(1, 1).surrounding().count(), 8);
+        assert_eq!(Position(1, 6).manhattan(Position(5, 11)), 9);
+    }
+    #[test]
+    fn points() {
+        let point = Point(0, 0).moved(Direction::North, 3);
+        assert_eq!(point, Point(0, -3));
+        assert_eq!(Position::try_from(point), Err(point));
+        assert_eq!(Position::try_from(Point(2, 3)), Ok(Position(2, 3)));
+        assert_eq!(point.manhattan(Point(-2, 1)), 6);
+        assert_eq!(Point(isize::MAX, 0).checked_moved(Direction::East, 1), None);
+        assert_eq!(Point(1, 1).neighbours().count(), 4);
+    }
+}
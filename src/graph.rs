@@ -0,0 +1,167 @@
+use std::{collections::HashMap, fmt, hash::Hash};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dot {
+    name: String,
+    statements: Vec<String>,
+}
+
+impl Dot {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            statements: vec![],
+        }
+    }
+
+    pub fn node(&mut self, id: &str, attributes: &[(&str, &str)]) -> &mut Self {
+        let statement = format!("{}{}", quote(id), format_attributes(attributes));
+        self.statements.push(statement);
+        self
+    }
+
+    pub fn edge(&mut self, from: &str, to: &str, attributes: &[(&str, &str)]) -> &mut Self {
+        let statement = format!(
+            "{} -> {}{}",
+            quote(from),
+            quote(to),
+            format_attributes(attributes)
+        );
+        self.statements.push(statement);
+        self
+    }
+}
+
+impl fmt::Display for Dot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph {} {{", quote(&self.name))?;
+        for statement in &self.statements {
+            writeln!(f, "    {statement};")?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn format_attributes(attributes: &[(&str, &str)]) -> String {
+    if attributes.is_empty() {
+        return String::new();
+    }
+    let attributes: Vec<_> = attributes
+        .iter()
+        .map(|(key, value)| format!("{key}={}", quote(value)))
+        .collect();
+    format!(" [{}]", attributes.join(", "))
+}
+
+// Tarjan's algorithm, giving each component after every component reachable from it
+pub fn strongly_connected_components<N, I>(
+    nodes: impl IntoIterator<Item = N>,
+    mut successors: impl FnMut(N) -> I,
+) -> Vec<Vec<N>>
+where
+    N: Copy + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut tarjan = Tarjan {
+        visits: HashMap::new(),
+        stack: vec![],
+        components: vec![],
+    };
+    let mut successors = |node| successors(node).into_iter().collect();
+    for node in nodes {
+        if !tarjan.visits.contains_key(&node) {
+            tarjan.visit(node, &mut successors);
+        }
+    }
+    tarjan.components
+}
+
+struct Tarjan<N> {
+    visits: HashMap<N, Visit>,
+    stack: Vec<N>,
+    components: Vec<Vec<N>>,
+}
+
+struct Visit {
+    index: usize,
+    on_stack: bool,
+}
+
+impl<N: Copy + Eq + Hash> Tarjan<N> {
+    // Returns the lowest index reachable from the node through nodes still on the stack
+    fn visit(&mut self, node: N, successors: &mut impl FnMut(N) -> Vec<N>) -> usize {
+        let index = self.visits.len();
+        self.visits.insert(
+            node,
+            Visit {
+                index,
+                on_stack: true,
+            },
+        );
+        self.stack.push(node);
+
+        let mut low_link = index;
+        for next in successors(node) {
+            match self.visits.get(&next) {
+                None => low_link = low_link.min(self.visit(next, successors)),
+                Some(visit) if visit.on_stack => low_link = low_link.min(visit.index),
+                Some(_) => {}
+            }
+        }
+
+        if low_link == index {
+            let mut component = vec![];
+            while let Some(member) = self.stack.pop() {
+                self.visits.get_mut(&member).unwrap().on_stack = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+        low_link
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dot() {
+        let mut dot = Dot::new("test");
+        dot.node("a", &[("shape", "box")])
+            .edge("a", "b \"quoted\"", &[])
+            .edge("b \"quoted\"", "a", &[("label", "x<2"), ("color", "red")]);
+        assert_eq!(
+            dot.to_string(),
+            "digraph \"test\" {
+    \"a\" [shape=\"box\"];
+    \"a\" -> \"b \\\"quoted\\\"\";
+    \"b \\\"quoted\\\"\" -> \"a\" [label=\"x<2\", color=\"red\"];
+}
+"
+        );
+    }
+    #[test]
+    fn components() {
+        let edges: HashMap<u8, Vec<u8>> = [
+            (1, vec![2]),
+            (2, vec![3, 4]),
+            (3, vec![1]),
+            (4, vec![5]),
+            (5, vec![5]),
+            (6, vec![4]),
+        ]
+        .into();
+        let mut components =
+            strongly_connected_components(1..=6, |node| edges[&node].iter().copied());
+        components.iter_mut().for_each(|component| component.sort());
+        assert_eq!(components, [vec![5], vec![4], vec![1, 2, 3], vec![6]]);
+    }
+}
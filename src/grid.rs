@@ -0,0 +1,267 @@
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use crate::{
+    geometry::{Direction, Position},
+    parse::{ParseResult, ParseStr},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(cells.len(), width * height, "Grid size doesn't match cells");
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(width, height, vec![value; width * height])
+    }
+
+    pub(crate) fn parse<'a>(
+        input: &'a str,
+        mut cell: impl FnMut(char) -> Option<T>,
+        reason: &'static str,
+    ) -> ParseResult<'a, Self> {
+        let width = input.find('\n').unwrap_or(input.len());
+        if width == 0 {
+            return Err(input.invalid("Empty grid"));
+        }
+        let mut cells = Vec::with_capacity(input.len());
+        let mut height = 0;
+        for line in input.split('\n') {
+            if line.len() != width {
+                return Err(line.invalid("Inconsistent row length"));
+            }
+            for (index, c) in line.char_indices() {
+                cells.push(cell(c).ok_or_else(|| line.char_at(index).invalid(reason))?);
+            }
+            height += 1;
+        }
+
+        Ok(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, Position(x, y): Position) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, position: Position) -> Option<&T> {
+        self.contains(position).then(|| &self[position])
+    }
+
+    pub fn get_mut(&mut self, position: Position) -> Option<&mut T> {
+        if self.contains(position) {
+            Some(&mut self[position])
+        } else {
+            None
+        }
+    }
+
+    pub fn step(&self, position: Position, direction: Direction) -> Option<Position> {
+        position
+            .checked_move(direction)
+            .filter(|position| self.contains(*position))
+    }
+
+    pub fn neighbours(
+        &self,
+        position: Position,
+    ) -> impl Iterator<Item = (Direction, Position)> + '_ {
+        position
+            .neighbours()
+            .filter(|(_, position)| self.contains(*position))
+    }
+
+    pub fn surrounding(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        position
+            .surrounding()
+            .filter(|position| self.contains(*position))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Position(x, y)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn find(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<Position> {
+        self.iter()
+            .find(|(_, cell)| predicate(cell))
+            .map(|(position, _)| position)
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        self.cells[x..].iter().step_by(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid::new(self.width, self.height, self.cells.iter().map(f).collect())
+    }
+
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        let cells = self.columns().flatten().cloned().collect();
+        Self::new(self.height, self.width, cells)
+    }
+
+    pub fn rotate_clockwise(&self) -> Self
+    where
+        T: Clone,
+    {
+        let cells = self
+            .columns()
+            .flat_map(|column| column.collect::<Vec<_>>().into_iter().rev())
+            .cloned()
+            .collect();
+        Self::new(self.height, self.width, cells)
+    }
+
+    pub fn rotate_anticlockwise(&self) -> Self
+    where
+        T: Clone,
+    {
+        let cells = (0..self.width)
+            .rev()
+            .flat_map(|x| self.column(x))
+            .cloned()
+            .collect();
+        Self::new(self.height, self.width, cells)
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, Position(x, y): Position) -> &Self::Output {
+        assert!(x < self.width, "Column {x} out of bounds");
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, Position(x, y): Position) -> &mut Self::Output {
+        assert!(x < self.width, "Column {x} out of bounds");
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                cell.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn grid(input: &str) -> Grid<char> {
+        Grid::parse(input, Some, "Unexpected").unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let grid = grid("abc\ndef");
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[Position(2, 1)], 'f');
+        assert_eq!(grid.get(Position(3, 0)), None);
+        assert_eq!(grid.to_string(), "abc\ndef");
+        let input = "ab\na";
+        let error = Grid::parse(input, Some, "Unexpected").unwrap_err();
+        assert_eq!(error.locate(0, input).line, 2);
+        let input = "ab\nax";
+        let error = Grid::parse(input, |c| (c != 'x').then_some(c), "Unexpected").unwrap_err();
+        assert_eq!(error.locate(0, input).column, 2);
+        for input in ["", "\n"] {
+            let error = Grid::parse(input, Some, "Unexpected").unwrap_err();
+            assert_eq!(error.locate(0, input).reason, "Empty grid");
+        }
+    }
+    #[test]
+    fn neighbours() {
+        let grid = grid("abc\ndef");
+        let neighbours: Vec<_> = grid.neighbours(Position(0, 0)).collect();
+        assert_eq!(
+            neighbours,
+            [
+                (Direction::East, Position(1, 0)),
+                (Direction::South, Position(0, 1))
+            ]
+        );
+        assert_eq!(grid.surrounding(Position(1, 0)).count(), 5);
+        assert_eq!(grid.step(Position(2, 1), Direction::East), None);
+    }
+    #[test]
+    fn rows_and_columns() {
+        let grid = grid("abc\ndef");
+        assert_eq!(
+            grid.rows().collect::<Vec<_>>(),
+            [['a', 'b', 'c'], ['d', 'e', 'f']]
+        );
+        assert_eq!(grid.column(1).collect::<String>(), "be");
+        assert_eq!(grid.columns().count(), 3);
+    }
+    #[test]
+    fn transform() {
+        let grid = grid("abc\ndef");
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf");
+        assert_eq!(grid.rotate_clockwise().to_string(), "da\neb\nfc");
+        assert_eq!(grid.rotate_anticlockwise().to_string(), "cf\nbe\nad");
+        assert_eq!(
+            grid.rotate_clockwise().rotate_anticlockwise(),
+            grid.rotate_anticlockwise().rotate_clockwise()
+        );
+    }
+}
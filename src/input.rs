@@ -0,0 +1,180 @@
+use std::{
+    collections::HashMap,
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+// When set, puzzle input tests fail rather than pass silently if their input is missing
+#[cfg(test)]
+const REQUIRE_INPUTS_VAR: &str = "AOC_REQUIRE_INPUTS";
+const DEFAULT_INPUT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+
+#[derive(Debug, Clone)]
+pub struct InputProvider {
+    directory: PathBuf,
+    paths: HashMap<u8, PathBuf>,
+}
+
+impl Default for InputProvider {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+impl InputProvider {
+    pub fn from_env() -> Self {
+        let directory = env::var_os(INPUT_DIR_VAR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_INPUT_DIR));
+        Self::with_directory(directory)
+    }
+
+    pub fn with_directory(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            paths: HashMap::new(),
+        }
+    }
+
+    pub fn with_path(mut self, day: u8, path: impl Into<PathBuf>) -> Self {
+        self.paths.insert(day, path.into());
+        self
+    }
+
+    pub fn load(&self, day: u8) -> Result<String, InputError> {
+        if let Some(path) = self.paths.get(&day) {
+            return read(path);
+        }
+        let candidates = self.candidates(day);
+        match candidates.iter().find(|path| path.is_file()) {
+            Some(path) => read(path),
+            None => bundled(day).map(str::to_owned).ok_or(InputError::Missing {
+                day,
+                searched: candidates,
+            }),
+        }
+    }
+
+    fn candidates(&self, day: u8) -> Vec<PathBuf> {
+        vec![
+            self.directory
+                .join(format!("day{day:02}"))
+                .join("input.txt"),
+            self.directory.join(format!("day{day:02}.txt")),
+        ]
+    }
+}
+
+fn read(path: &Path) -> Result<String, InputError> {
+    fs::read_to_string(path)
+        .map(|input| input.trim_end_matches('\n').to_owned())
+        .map_err(|error| InputError::Io {
+            path: path.to_owned(),
+            error,
+        })
+}
+
+#[cfg(feature = "bundled-inputs")]
+fn bundled(day: u8) -> Option<&'static str> {
+    use crate::*;
+
+    Some(match day {
+        1 => day01::PUZZLE_INPUT,
+        2 => day02::PUZZLE_INPUT,
+        3 => day03::PUZZLE_INPUT,
+        4 => day04::PUZZLE_INPUT,
+        5 => day05::PUZZLE_INPUT,
+        6 => day06::PUZZLE_INPUT,
+        7 => day07::PUZZLE_INPUT,
+        8 => day08::PUZZLE_INPUT,
+        9 => day09::PUZZLE_INPUT,
+        10 => day10::PUZZLE_INPUT,
+        11 => day11::PUZZLE_INPUT,
+        12 => day12::PUZZLE_INPUT,
+        13 => day13::PUZZLE_INPUT,
+        14 => day14::PUZZLE_INPUT,
+        15 => day15::PUZZLE_INPUT,
+        16 => day16::PUZZLE_INPUT,
+        17 => day17::PUZZLE_INPUT,
+        18 => day18::PUZZLE_INPUT,
+        19 => day19::PUZZLE_INPUT,
+        20 => day20::PUZZLE_INPUT,
+        21 => day21::PUZZLE_INPUT,
+        22 => day22::PUZZLE_INPUT,
+        _ => return None,
+    })
+}
+
+#[cfg(not(feature = "bundled-inputs"))]
+fn bundled(_day: u8) -> Option<&'static str> {
+    None
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Missing { day: u8, searched: Vec<PathBuf> },
+    Io { path: PathBuf, error: io::Error },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Missing { day, searched } => {
+                write!(f, "No input for day {day}, looked for")?;
+                for path in searched {
+                    write!(f, " {}", path.display())?;
+                }
+                write!(f, " (set {INPUT_DIR_VAR} to use another directory)")
+            }
+            InputError::Io { path, error } => {
+                write!(f, "Couldn't read {}: {error}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+#[cfg(test)]
+pub(crate) fn puzzle_input(day: u8) -> Option<String> {
+    match InputProvider::from_env().load(day) {
+        Ok(input) => Some(input),
+        Err(error) if env::var_os(REQUIRE_INPUTS_VAR).is_some() => panic!("{error}"),
+        Err(error) => {
+            eprintln!("Skipping puzzle input test: {error}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_input() {
+        let provider = InputProvider::with_directory("does/not/exist");
+        let error = provider.load(99).unwrap_err();
+        assert!(matches!(error, InputError::Missing { day: 99, .. }));
+    }
+    #[test]
+    fn explicit_path() {
+        let path = env::temp_dir().join("aoc2023_explicit_path.txt");
+        fs::write(&path, "1abc2\n").unwrap();
+        let provider = InputProvider::with_directory("does/not/exist").with_path(1, &path);
+        assert_eq!(provider.load(1).unwrap(), "1abc2");
+        fs::remove_file(path).unwrap();
+    }
+    #[test]
+    fn directory_layouts() {
+        let directory = env::temp_dir().join("aoc2023_directory_layouts");
+        fs::create_dir_all(directory.join("day02")).unwrap();
+        fs::write(directory.join("day02").join("input.txt"), "nested").unwrap();
+        fs::write(directory.join("day03.txt"), "flat").unwrap();
+        let provider = InputProvider::with_directory(&directory);
+        assert_eq!(provider.load(2).unwrap(), "nested");
+        assert_eq!(provider.load(3).unwrap(), "flat");
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,284 @@
+use std::{cmp::Ordering, ops::Range};
+
+use num::{PrimInt, Signed};
+
+// Sorted, disjoint and non-adjacent half-open ranges
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Copy + Ord> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: vec![] }
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|range| range.start)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.ranges
+            .binary_search_by(|range| compare(range, value))
+            .is_ok()
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        self.ranges.push(range);
+        self.coalesce();
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.ranges.iter().chain(&other.ranges).cloned().collect()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let (mut a, mut b) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            let overlap = x.start.max(y.start)..x.end.min(y.end);
+            if overlap.start < overlap.end {
+                ranges.push(overlap);
+            }
+            if x.end < y.end {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let mut others = other.ranges.iter().peekable();
+        for range in &self.ranges {
+            let mut start = range.start;
+            while let Some(other) = others.peek() {
+                if other.end <= start {
+                    others.next();
+                    continue;
+                }
+                if other.start >= range.end {
+                    break;
+                }
+                if other.start > start {
+                    ranges.push(start..other.start);
+                }
+                start = other.end;
+                if other.end > range.end {
+                    break;
+                }
+                others.next();
+            }
+            if start < range.end {
+                ranges.push(start..range.end);
+            }
+        }
+        Self { ranges }
+    }
+
+    fn coalesce(&mut self) {
+        self.ranges.retain(|range| range.start < range.end);
+        self.ranges.sort_unstable_by_key(|range| range.start);
+        let mut coalesced: Vec<Range<T>> = Vec::with_capacity(self.ranges.len());
+        for range in self.ranges.drain(..) {
+            match coalesced.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => coalesced.push(range),
+            }
+        }
+        self.ranges = coalesced;
+    }
+}
+
+impl<T: Copy + Ord> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Ord> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        Self::from_iter([range])
+    }
+}
+
+impl<T: Copy + Ord> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = Self {
+            ranges: iter.into_iter().collect(),
+        };
+        set.coalesce();
+        set
+    }
+}
+
+fn compare<T: Ord>(range: &Range<T>, value: T) -> Ordering {
+    if range.end <= value {
+        Ordering::Less
+    } else if range.start > value {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+fn shift<T: PrimInt>(range: &Range<T>, offset: T) -> Range<T> {
+    range.start + offset..range.end + offset
+}
+
+// A function adding a constant offset on each of its pieces, and the identity elsewhere
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PiecewiseLinear<T> {
+    pieces: Vec<(Range<T>, T)>,
+}
+
+impl<T: PrimInt + Signed> PiecewiseLinear<T> {
+    pub fn identity() -> Self {
+        Self { pieces: vec![] }
+    }
+
+    pub fn pieces(&self) -> &[(Range<T>, T)] {
+        &self.pieces
+    }
+
+    pub fn domain(&self) -> IntervalSet<T> {
+        self.pieces.iter().map(|(range, _)| range.clone()).collect()
+    }
+
+    pub fn apply(&self, value: T) -> T {
+        match self
+            .pieces
+            .binary_search_by(|(range, _)| compare(range, value))
+        {
+            Ok(index) => value + self.pieces[index].1,
+            Err(_) => value,
+        }
+    }
+
+    pub fn image(&self, set: &IntervalSet<T>) -> IntervalSet<T> {
+        let mapped = self.pieces.iter().flat_map(|(range, offset)| {
+            set.intersection(&range.clone().into())
+                .ranges
+                .into_iter()
+                .map(|range| shift(&range, *offset))
+        });
+        let unmapped = set.difference(&self.domain()).ranges;
+        mapped.chain(unmapped).collect()
+    }
+
+    // The function applying `self` and then `next`
+    pub fn then(&self, next: &Self) -> Self {
+        let next_domain = next.domain();
+        let mut pieces = vec![];
+        for (range, offset) in &self.pieces {
+            let image = IntervalSet::from(shift(range, *offset));
+            for (next_range, next_offset) in &next.pieces {
+                for overlap in image.intersection(&next_range.clone().into()).ranges {
+                    pieces.push((shift(&overlap, -*offset), *offset + *next_offset));
+                }
+            }
+            for rest in image.difference(&next_domain).ranges {
+                pieces.push((shift(&rest, -*offset), *offset));
+            }
+        }
+        let domain = self.domain();
+        for (next_range, next_offset) in &next.pieces {
+            for rest in IntervalSet::from(next_range.clone())
+                .difference(&domain)
+                .ranges
+            {
+                pieces.push((rest, *next_offset));
+            }
+        }
+        pieces.into_iter().collect()
+    }
+}
+
+// Earlier pieces take precedence where pieces overlap
+impl<T: PrimInt + Signed> FromIterator<(Range<T>, T)> for PiecewiseLinear<T> {
+    fn from_iter<I: IntoIterator<Item = (Range<T>, T)>>(iter: I) -> Self {
+        let mut covered = IntervalSet::new();
+        let mut pieces = vec![];
+        for (range, offset) in iter {
+            let range = IntervalSet::from(range);
+            for uncovered in range.difference(&covered).ranges {
+                pieces.push((uncovered, offset));
+            }
+            covered = covered.union(&range);
+        }
+        pieces.retain(|(_, offset)| !offset.is_zero());
+        pieces.sort_unstable_by_key(|(range, _)| range.start);
+
+        let mut merged: Vec<(Range<T>, T)> = Vec::with_capacity(pieces.len());
+        for (range, offset) in pieces {
+            match merged.last_mut() {
+                Some((last, last_offset)) if last.end == range.start && *last_offset == offset => {
+                    last.end = range.end
+                }
+                _ => merged.push((range, offset)),
+            }
+        }
+        Self { pieces: merged }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set(ranges: &[Range<i64>]) -> IntervalSet<i64> {
+        ranges.iter().cloned().collect()
+    }
+
+    #[test]
+    fn coalesce() {
+        assert_eq!(set(&[5..8, 0..2, 2..3, 7..9, 4..4]).ranges(), [0..3, 5..9]);
+        let mut intervals = IntervalSet::from(0..2);
+        intervals.insert(1..5);
+        assert_eq!(intervals, IntervalSet::from(0..5));
+        assert!(intervals.contains(4) && !intervals.contains(5));
+    }
+    #[test]
+    fn set_operations() {
+        let a = set(&[0..5, 10..15]);
+        let b = set(&[3..12, 14..20]);
+        assert_eq!(a.union(&b), IntervalSet::from(0..20));
+        assert_eq!(a.intersection(&b).ranges(), [3..5, 10..12, 14..15]);
+        assert_eq!(a.difference(&b).ranges(), [0..3, 12..14]);
+        assert_eq!(b.difference(&a).ranges(), [5..10, 15..20]);
+        assert_eq!(a.difference(&(-5..30).into()), IntervalSet::new());
+        assert_eq!(
+            a.difference(&set(&[1..2, 3..4])).ranges(),
+            [0..1, 2..3, 4..5, 10..15]
+        );
+    }
+    #[test]
+    fn piecewise_linear() {
+        let f: PiecewiseLinear<i64> = [(0..10, 100), (5..20, -5), (20..25, 0)]
+            .into_iter()
+            .collect();
+        assert_eq!(f.pieces(), [(0..10, 100), (10..20, -5)]);
+        assert_eq!((f.apply(3), f.apply(12), f.apply(22)), (103, 7, 22));
+        assert_eq!(f.image(&(8..12).into()).ranges(), [5..7, 108..110]);
+
+        let g: PiecewiseLinear<i64> = [(100..105, 1), (6..8, 1000)].into_iter().collect();
+        let composed = f.then(&g);
+        for value in -5..30 {
+            assert_eq!(composed.apply(value), g.apply(f.apply(value)), "{value}");
+        }
+        assert_eq!(f.then(&PiecewiseLinear::identity()), f);
+    }
+}
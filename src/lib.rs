@@ -1,3 +1,4 @@
+pub mod cycle;
 pub mod day01;
 pub mod day02;
 pub mod day03;
@@ -20,4 +21,12 @@ pub mod day19;
 pub mod day20;
 pub mod day21;
 pub mod day22;
+pub mod geometry;
+pub mod graph;
+pub mod grid;
+pub mod input;
+pub mod interval;
+pub mod parse;
+pub mod polygon;
+pub mod solution;
 pub mod two_iter;
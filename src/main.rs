@@ -0,0 +1,110 @@
+use std::{
+    env, fs,
+    io::{self, Read},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use aoc2023::{
+    input::InputProvider,
+    solution::{Answer, Part},
+    *,
+};
+
+const USAGE: &str = "Usage:
+    aoc2023 <day> <part> [input]    Solve a single part, reading input from a file or `-` for stdin
+    aoc2023 all                     Solve every day using the puzzle inputs
+
+Puzzle inputs are read from $AOC_INPUT_DIR/dayNN/input.txt or $AOC_INPUT_DIR/dayNN.txt";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [all] if all == "all" => {
+            run_all();
+            ExitCode::SUCCESS
+        }
+        [day, part, rest @ ..] if rest.len() <= 1 => match run_one(day, part, rest.first()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{error}");
+                ExitCode::FAILURE
+            }
+        },
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_one(day: &str, part: &str, input: Option<&String>) -> Result<(), String> {
+    let day: u8 = day.parse().map_err(|_| format!("Invalid day: {day}"))?;
+    let part = part
+        .parse()
+        .ok()
+        .and_then(|part: u8| Part::try_from(part).ok())
+        .ok_or_else(|| format!("Invalid part: {part}"))?;
+    let puzzle = solution::day(day).ok_or_else(|| format!("No solution for day {day}"))?;
+    let input = match input.map(String::as_str) {
+        None => InputProvider::from_env()
+            .load(day)
+            .map_err(|error| error.to_string())?,
+        Some("-") => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|error| format!("Couldn't read stdin: {error}"))?;
+            input
+        }
+        Some(path) => {
+            fs::read_to_string(path).map_err(|error| format!("Couldn't read {path}: {error}"))?
+        }
+    };
+    let input = input.trim_end_matches('\n');
+
+    let (answer, elapsed) = timed(|| puzzle.solve(input, part));
+    print_answer(
+        day,
+        part,
+        &answer.map_err(|error| error.to_string())?,
+        elapsed,
+    );
+    Ok(())
+}
+
+fn run_all() {
+    let provider = InputProvider::from_env();
+    let mut total = Duration::ZERO;
+    for puzzle in solution::DAYS {
+        let day = puzzle.day();
+        let input = match provider.load(day) {
+            Ok(input) => input,
+            Err(error) => {
+                println!("Day {day:02}: {error}");
+                continue;
+            }
+        };
+        for part in Part::ALL {
+            match timed(|| puzzle.solve(&input, part)) {
+                (Ok(answer), elapsed) => {
+                    total += elapsed;
+                    print_answer(day, part, &answer, elapsed);
+                }
+                (Err(error), _) => println!("{error}"),
+            }
+        }
+    }
+    println!("Total: {total:.2?}");
+}
+
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+fn print_answer(day: u8, part: Part, answer: &Answer, elapsed: Duration) {
+    println!("Day {day:02} part {part}: {answer} ({elapsed:.2?})");
+}
@@ -0,0 +1,147 @@
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Day {} line {} column {}: {} {:?}",
+            self.day, self.line, self.column, self.reason, self.text
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Invalid<'a> {
+    text: &'a str,
+    reason: &'static str,
+}
+
+pub(crate) type ParseResult<'a, T> = Result<T, Invalid<'a>>;
+
+impl<'a> Invalid<'a> {
+    pub(crate) fn new(text: &'a str, reason: &'static str) -> Self {
+        Self { text, reason }
+    }
+
+    pub(crate) fn locate(self, day: u8, input: &str) -> ParseError {
+        let start = input.as_ptr() as usize;
+        let offset = (self.text.as_ptr() as usize)
+            .checked_sub(start)
+            .filter(|offset| *offset <= input.len());
+        let (line, column) = match offset {
+            Some(offset) => {
+                let before = &input[..offset];
+                let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+                (
+                    before.matches('\n').count() + 1,
+                    before[line_start..].chars().count() + 1,
+                )
+            }
+            None => (0, 0),
+        };
+
+        ParseError {
+            day,
+            line,
+            column,
+            text: self.text.to_owned(),
+            reason: self.reason,
+        }
+    }
+}
+
+pub(crate) trait ParseStr<'a> {
+    fn invalid(self, reason: &'static str) -> Invalid<'a>;
+    fn split_once_or(
+        self,
+        delimiter: &str,
+        reason: &'static str,
+    ) -> ParseResult<'a, (&'a str, &'a str)>;
+    fn strip_prefix_or(self, prefix: &str, reason: &'static str) -> ParseResult<'a, &'a str>;
+    fn strip_suffix_or(self, suffix: &str, reason: &'static str) -> ParseResult<'a, &'a str>;
+    fn parse_or<T: FromStr>(self, reason: &'static str) -> ParseResult<'a, T>;
+    fn char_at(self, index: usize) -> &'a str;
+}
+
+impl<'a> ParseStr<'a> for &'a str {
+    fn invalid(self, reason: &'static str) -> Invalid<'a> {
+        Invalid::new(self, reason)
+    }
+
+    fn split_once_or(
+        self,
+        delimiter: &str,
+        reason: &'static str,
+    ) -> ParseResult<'a, (&'a str, &'a str)> {
+        self.split_once(delimiter).ok_or(Invalid::new(self, reason))
+    }
+
+    fn strip_prefix_or(self, prefix: &str, reason: &'static str) -> ParseResult<'a, &'a str> {
+        self.strip_prefix(prefix).ok_or(Invalid::new(self, reason))
+    }
+
+    fn strip_suffix_or(self, suffix: &str, reason: &'static str) -> ParseResult<'a, &'a str> {
+        self.strip_suffix(suffix).ok_or(Invalid::new(self, reason))
+    }
+
+    fn parse_or<T: FromStr>(self, reason: &'static str) -> ParseResult<'a, T> {
+        self.parse().map_err(|_| Invalid::new(self, reason))
+    }
+
+    fn char_at(self, index: usize) -> &'a str {
+        let len = self[index..].chars().next().map_or(0, char::len_utf8);
+        &self[index..index + len]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn locate_error() {
+        let input = "12 34\n56 7x\n90";
+        let line = input.split('\n').nth(1).unwrap();
+        let (_, text) = line.split_once(' ').unwrap();
+        let error = text
+            .parse_or::<u32>("Invalid number")
+            .unwrap_err()
+            .locate(3, input);
+        assert_eq!(
+            error,
+            ParseError {
+                day: 3,
+                line: 2,
+                column: 4,
+                text: "7x".to_owned(),
+                reason: "Invalid number"
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Day 3 line 2 column 4: Invalid number \"7x\""
+        );
+    }
+    #[test]
+    fn locate_foreign_text() {
+        let input = String::from("input");
+        let error = "elsewhere".invalid("Unexpected").locate(1, &input);
+        assert_eq!((error.line, error.column), (0, 0));
+    }
+    #[test]
+    fn char_at() {
+        assert_eq!("a→b".char_at(1), "→");
+        assert_eq!("ab".char_at(2), "");
+    }
+}
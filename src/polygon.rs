@@ -0,0 +1,159 @@
+use std::fmt;
+
+use num::Integer;
+
+use crate::geometry::{Direction, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Polygon is too large to measure")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+// Orientation as drawn in the input, with y increasing southwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Clockwise,
+    Anticlockwise,
+    Degenerate,
+}
+
+// The closed polygon through the vertices in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<Point>) -> Self {
+        Self { vertices }
+    }
+
+    // Starts at the origin and adds a vertex at the end of every move
+    pub fn from_instructions(
+        instructions: impl IntoIterator<Item = (Direction, isize)>,
+    ) -> Result<Self, Overflow> {
+        let mut corner = Point::default();
+        let vertices = instructions
+            .into_iter()
+            .map(|(direction, distance)| {
+                corner = corner.checked_moved(direction, distance).ok_or(Overflow)?;
+                Ok(corner)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { vertices })
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    // Twice the area from the shoelace formula, positive for clockwise polygons
+    pub fn signed_double_area(&self) -> Result<isize, Overflow> {
+        self.edges()
+            .try_fold(0isize, |area, (Point(x1, y1), Point(x2, y2))| {
+                x1.checked_mul(y2)
+                    .zip(x2.checked_mul(y1))
+                    .and_then(|(a, b)| a.checked_sub(b))
+                    .and_then(|cross| area.checked_add(cross))
+                    .ok_or(Overflow)
+            })
+    }
+
+    pub fn orientation(&self) -> Result<Orientation, Overflow> {
+        Ok(match self.signed_double_area()?.signum() {
+            1 => Orientation::Clockwise,
+            -1 => Orientation::Anticlockwise,
+            _ => Orientation::Degenerate,
+        })
+    }
+
+    // The length of the boundary, measuring every edge along the grid as for rectilinear polygons
+    pub fn perimeter(&self) -> Result<usize, Overflow> {
+        self.edges()
+            .try_fold(0usize, |perimeter, (a, b)| {
+                perimeter.checked_add(a.manhattan(b))
+            })
+            .ok_or(Overflow)
+    }
+
+    pub fn boundary_points(&self) -> Result<usize, Overflow> {
+        self.edges()
+            .try_fold(0usize, |points, (Point(x1, y1), Point(x2, y2))| {
+                points.checked_add(x1.abs_diff(x2).gcd(&y1.abs_diff(y2)))
+            })
+            .ok_or(Overflow)
+    }
+
+    // Pick's theorem: area = interior + boundary / 2 - 1
+    pub fn interior_points(&self) -> Result<usize, Overflow> {
+        let double_area = self.signed_double_area()?.unsigned_abs();
+        let boundary = self.boundary_points()?;
+        Ok((double_area + 2).saturating_sub(boundary) / 2)
+    }
+
+    // The area covered by unit squares centred on every lattice point inside or on the
+    // boundary, as when the boundary is a trench one square wide
+    pub fn thick_area(&self) -> Result<usize, Overflow> {
+        self.interior_points()?
+            .checked_add(self.boundary_points()?)
+            .ok_or(Overflow)
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let vertices = self.vertices.iter().copied();
+        vertices.clone().zip(vertices.cycle().skip(1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn square() {
+        let square = Polygon::new(vec![Point(0, 0), Point(4, 0), Point(4, 4), Point(0, 4)]);
+        assert_eq!(square.signed_double_area(), Ok(32));
+        assert_eq!(square.orientation(), Ok(Orientation::Clockwise));
+        assert_eq!(square.perimeter(), Ok(16));
+        assert_eq!(square.boundary_points(), Ok(16));
+        assert_eq!(square.interior_points(), Ok(9));
+        assert_eq!(square.thick_area(), Ok(25));
+        let reversed = Polygon::new(square.vertices().iter().rev().copied().collect());
+        assert_eq!(reversed.signed_double_area(), Ok(-32));
+        assert_eq!(reversed.orientation(), Ok(Orientation::Anticlockwise));
+    }
+    #[test]
+    fn triangle() {
+        let triangle = Polygon::new(vec![Point(0, 0), Point(4, 2), Point(0, 4)]);
+        assert_eq!(triangle.boundary_points(), Ok(2 + 2 + 4));
+        assert_eq!(triangle.interior_points(), Ok(5));
+    }
+    #[test]
+    fn instructions() {
+        use Direction::*;
+        let polygon =
+            Polygon::from_instructions([(East, 2), (South, 3), (West, 2), (North, 3)]).unwrap();
+        assert_eq!(
+            polygon.vertices(),
+            [Point(2, 0), Point(2, 3), Point(0, 3), Point(0, 0)]
+        );
+        assert_eq!(polygon.thick_area(), Ok(12));
+        assert_eq!(
+            Polygon::from_instructions([(East, isize::MAX), (East, 1)]),
+            Err(Overflow)
+        );
+        let huge = Polygon::from_instructions([(East, 1 << 40), (South, 1 << 40), (West, 1 << 40)])
+            .unwrap();
+        assert_eq!(huge.signed_double_area(), Err(Overflow));
+        assert_eq!(
+            Polygon::new(vec![Point(0, 0), Point(3, 0)]).orientation(),
+            Ok(Orientation::Degenerate)
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use std::fmt;
+
+use crate::{parse::ParseError, *};
+
+pub static DAYS: [&dyn Puzzle; 22] = [
+    &day01::Day01,
+    &day02::Day02,
+    &day03::Day03,
+    &day04::Day04,
+    &day05::Day05,
+    &day06::Day06,
+    &day07::Day07,
+    &day08::Day08,
+    &day09::Day09,
+    &day10::Day10,
+    &day11::Day11,
+    &day12::Day12,
+    &day13::Day13,
+    &day14::Day14,
+    &day15::Day15,
+    &day16::Day16,
+    &day17::Day17,
+    &day18::Day18,
+    &day19::Day19,
+    &day20::Day20,
+    &day21::Day21,
+    &day22::Day22,
+];
+
+pub fn day(day: u8) -> Option<&'static dyn Puzzle> {
+    DAYS.iter().copied().find(|puzzle| puzzle.day() == day)
+}
+
+pub trait Solution {
+    const DAY: u8;
+    type Parsed<'a>;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError>;
+    fn part1(parsed: &Self::Parsed<'_>) -> Result<Answer, SolveError>;
+    fn part2(parsed: &Self::Parsed<'_>) -> Result<Answer, SolveError>;
+}
+
+pub trait Puzzle: Sync {
+    fn day(&self) -> u8;
+    fn parse<'a>(&self, input: &'a str) -> Result<Box<dyn Parsed + 'a>, ParseError>;
+
+    fn solve(&self, input: &str, part: Part) -> Result<Answer, PuzzleError> {
+        let parsed = self.parse(input)?;
+        match part {
+            Part::One => parsed.part1(),
+            Part::Two => parsed.part2(),
+        }
+        .map_err(|error| PuzzleError::Solve {
+            day: self.day(),
+            part,
+            error,
+        })
+    }
+}
+
+// Input that parsed but has no answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveError {
+    pub reason: String,
+}
+
+impl SolveError {
+    pub fn new(reason: impl fmt::Display) -> Self {
+        Self {
+            reason: reason.to_string(),
+        }
+    }
+}
+
+// For errors located in the input but only found while solving, leaving the day to `PuzzleError`
+impl From<ParseError> for SolveError {
+    fn from(error: ParseError) -> Self {
+        Self::new(format!(
+            "line {} column {}: {} {:?}",
+            error.line, error.column, error.reason, error.text
+        ))
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleError {
+    Parse(ParseError),
+    Solve {
+        day: u8,
+        part: Part,
+        error: SolveError,
+    },
+}
+
+impl From<ParseError> for PuzzleError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleError::Parse(error) => error.fmt(f),
+            PuzzleError::Solve { day, part, error } => write!(f, "Day {day} part {part}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub const ALL: [Self; 2] = [Self::One, Self::Two];
+}
+
+impl TryFrom<u8> for Part {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::One),
+            2 => Ok(Self::Two),
+            _ => Err(value),
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Part::One => write!(f, "1"),
+            Part::Two => write!(f, "2"),
+        }
+    }
+}
+
+pub trait Parsed {
+    fn part1(&self) -> Result<Answer, SolveError>;
+    fn part2(&self) -> Result<Answer, SolveError>;
+}
+
+impl<S: Solution + Sync + 'static> Puzzle for S {
+    fn day(&self) -> u8 {
+        S::DAY
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<Box<dyn Parsed + 'a>, ParseError> {
+        Ok(Box::new(Prepared::<S>(S::parse(input)?)))
+    }
+}
+
+struct Prepared<'a, S: Solution>(S::Parsed<'a>);
+
+impl<S: Solution> Parsed for Prepared<'_, S> {
+    fn part1(&self) -> Result<Answer, SolveError> {
+        S::part1(&self.0)
+    }
+
+    fn part2(&self) -> Result<Answer, SolveError> {
+        S::part2(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    U128(u128),
+    Isize(isize),
+    Usize(usize),
+}
+
+macro_rules! impl_from_answer {
+    ($($variant:ident($type:ty)),*) => {
+        $(
+            impl From<$type> for Answer {
+                fn from(value: $type) -> Self {
+                    Self::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_answer!(
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    U128(u128),
+    Isize(isize),
+    Usize(usize)
+);
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::I32(value) => value.fmt(f),
+            Answer::U32(value) => value.fmt(f),
+            Answer::I64(value) => value.fmt(f),
+            Answer::U64(value) => value.fmt(f),
+            Answer::U128(value) => value.fmt(f),
+            Answer::Isize(value) => value.fmt(f),
+            Answer::Usize(value) => value.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn registry_is_ordered_by_day() {
+        assert!(DAYS
+            .iter()
+            .zip(1..)
+            .all(|(puzzle, day)| puzzle.day() == day));
+    }
+    #[test]
+    fn solve_through_registry() {
+        let input = "Time:      7  15   30\nDistance:  9  40  200";
+        assert_eq!(
+            day(6).unwrap().solve(input, Part::One),
+            Ok(Answer::U64(288))
+        );
+        assert_eq!(
+            day(6).unwrap().solve(input, Part::Two),
+            Ok(Answer::U64(71503))
+        );
+        assert!(day(23).is_none());
+    }
+    #[test]
+    fn parse_error_through_registry() {
+        let error = day(6).unwrap().solve("Time: 7\nDistance: x", Part::One);
+        let Err(PuzzleError::Parse(error)) = error else {
+            panic!("Expected a parse error, got {error:?}");
+        };
+        assert_eq!(error.line, 2);
+    }
+    #[test]
+    fn solve_error_through_registry() {
+        let error = day(17).unwrap().solve("12\n34", Part::Two).unwrap_err();
+        assert_eq!(
+            error,
+            PuzzleError::Solve {
+                day: 17,
+                part: Part::Two,
+                error: SolveError::new("No route through the city"),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Day 17 part 2: No route through the city"
+        );
+    }
+}
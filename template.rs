@@ -1,16 +1,64 @@
+use crate::{
+    parse::{ParseError, ParseResult},
+    solution::{Answer, Solution, SolveError},
+};
+
+#[cfg(feature = "bundled-inputs")]
 pub const PUZZLE_INPUT: &str = include_str!("input.txt");
 
+pub struct DayNN;
+
+impl Solution for DayNN {
+    const DAY: u8 = NN;
+    type Parsed<'a> = Puzzle;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, ParseError> {
+        Puzzle::parse(input).map_err(|error| error.locate(Self::DAY, input))
+    }
+    fn part1(puzzle: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(puzzle.part1().into())
+    }
+    fn part2(puzzle: &Self::Parsed<'_>) -> Result<Answer, SolveError> {
+        Ok(puzzle.part2().into())
+    }
+}
+
 pub fn part1(input: &str) -> usize {
-    todo!();
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    todo!();
+    try_part2(input).unwrap()
+}
+
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+    Ok(DayNN::parse(input)?.part1())
+}
+
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+    Ok(DayNN::parse(input)?.part2())
+}
+
+pub struct Puzzle {}
+
+impl Puzzle {
+    fn parse(input: &str) -> ParseResult<'_, Self> {
+        todo!();
+    }
+
+    fn part1(&self) -> usize {
+        todo!();
+    }
+
+    fn part2(&self) -> usize {
+        todo!();
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::input::puzzle_input;
 
     const TEST_INPUT: &str = r#""#;
 
@@ -20,7 +68,15 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part1() {
-        assert_eq!(part1(PUZZLE_INPUT), 0);
+        let Some(input) = puzzle_input(NN) else {
+            return;
+        };
+        assert_eq!(part1(&input), 0);
+    }
+    #[test]
+    fn malformed_input() {
+        let error = try_part1("").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 1));
     }
     #[test]
     fn test_input_part2() {
@@ -28,6 +84,9 @@ mod test {
     }
     #[test]
     fn test_puzzle_input_part2() {
-        assert_eq!(part2(PUZZLE_INPUT), 0);
+        let Some(input) = puzzle_input(NN) else {
+            return;
+        };
+        assert_eq!(part2(&input), 0);
     }
 }